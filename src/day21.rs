@@ -22,7 +22,7 @@ fn compute(lists: Vec<IngredientList<'_>>) -> u32 {
         for &allergin in &list.allergins {
             map.entry(allergin)
                 .and_modify(|i| {
-                    *i = list.ingredients.intersection(&i).copied().collect();
+                    *i = list.ingredients.intersection(i).copied().collect();
                 })
                 .or_insert(list.ingredients.clone());
         }
@@ -51,7 +51,7 @@ fn compute_2(lists: Vec<IngredientList<'_>>) -> Vec<&str> {
         for &allergin in &list.allergins {
             map.entry(allergin)
                 .and_modify(|i| {
-                    *i = list.ingredients.intersection(&i).copied().collect();
+                    *i = list.ingredients.intersection(i).copied().collect();
                 })
                 .or_insert(list.ingredients.clone());
         }
@@ -80,7 +80,7 @@ fn compute_2(lists: Vec<IngredientList<'_>>) -> Vec<&str> {
         .collect()
 }
 
-fn parse(input: &str) -> Vec<IngredientList> {
+fn parse(input: &str) -> Vec<IngredientList<'_>> {
     fn allergins(input: &str) -> IResult<&str, Vec<&str>> {
         map(
             opt(delimited(
@@ -94,7 +94,7 @@ fn parse(input: &str) -> Vec<IngredientList> {
     fn ingredients(input: &str) -> IResult<&str, Vec<&str>> {
         separated_list1(space1, alpha1)(input)
     }
-    fn ingredient_list(input: &str) -> IResult<&str, IngredientList> {
+    fn ingredient_list(input: &str) -> IResult<&str, IngredientList<'_>> {
         let ingredients = preceded(multispace0, ingredients);
         let allergins = preceded(space0, allergins);
         map(pair(ingredients, allergins), |(i, a)| IngredientList {
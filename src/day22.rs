@@ -1,137 +1,375 @@
 use std::{
-    collections::{HashSet, VecDeque},
-    iter::zip,
+    collections::HashSet,
+    iter::{once, zip},
 };
 
+use derive_more::Display;
 use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, multispace0},
-    combinator::{map, map_res},
+    combinator::map_res,
     error::ParseError,
-    multi::{many0, many1},
-    sequence::{delimited, preceded, tuple},
+    multi::many0,
+    sequence::{delimited, pair},
     IResult,
 };
 use std::str::FromStr;
 
-#[derive(PartialEq, Eq, Debug, Hash, Clone)]
-struct Deck(VecDeque<u32>);
+use self::{
+    deck::Deck,
+    rules::{Normal, Recursive, Rules, Tie, Winner},
+    transcript::Log,
+};
+
+mod deck;
+mod rules;
+mod search;
+mod simulate;
+mod transcript;
+mod viewer;
+
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// Print the round-by-round transcript of the game
+    #[arg(long)]
+    transcript: bool,
+    /// Step through the game interactively
+    #[arg(long)]
+    step: bool,
+    /// Play this many random deals instead of the input, print statistics
+    /// about them and answer with the number of games
+    #[arg(long, value_name = "GAMES", conflicts_with_all = ["transcript", "step", "search"])]
+    simulate: Option<usize>,
+    /// Search for the deal that maximises this instead of playing the
+    /// input, print it and answer with the maximum
+    #[arg(
+        long,
+        value_enum,
+        value_name = "TARGET",
+        conflicts_with_all = ["transcript", "step"]
+    )]
+    search: Option<search::Target>,
+    /// The number of deals to try when searching by hill-climbing
+    #[arg(long, default_value_t = 1000)]
+    search_steps: usize,
+    /// The number of players in random deals
+    #[arg(long, default_value_t = 2)]
+    players: usize,
+    /// The number of cards dealt to each player in random deals
+    #[arg(long, default_value_t = 10)]
+    deck_size: usize,
+    /// The seed for random deals
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// What happens when players tie with the highest card
+    #[arg(long, value_enum, default_value_t = Tie::Error)]
+    tie: Tie,
+}
 
-impl Deck {
-    fn score(&self) -> u64 {
-        let mut result = 0;
-        for (i, &card) in self.0.iter().rev().enumerate() {
-            result += (i as u64 + 1) * card as u64;
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            transcript: false,
+            step: false,
+            simulate: None,
+            search: None,
+            search_steps: 1000,
+            players: 2,
+            deck_size: 10,
+            seed: 0,
+            tie: Tie::Error,
         }
-        result
     }
+}
 
-    fn truncate(&self, len: usize) -> Self {
-        Self(self.0.iter().copied().take(len).collect())
-    }
+#[derive(Clone, Debug, PartialEq, Eq, Display)]
+pub(crate) enum Error {
+    #[display(fmt = "Invalid input at {:?}", _0)]
+    Parse(String),
+    #[display(fmt = "Expected 1 to {} players, found {}", MAX_PLAYERS, _0)]
+    Players(usize),
+    #[display(fmt = "Expected player {}, found player {}", expected, found)]
+    Player { expected: usize, found: usize },
+    #[display(fmt = "Player {}'s deck is empty", _0)]
+    EmptyDeck(usize),
+    #[display(fmt = "Card {} is dealt more than once", _0)]
+    DuplicateCard(u8),
+    #[display(fmt = "Expected at most {} cards in all, found {}", deck::CAPACITY, _0)]
+    TooManyCards(usize),
+    #[display(
+        fmt = "Can't deal {} different cards to each of {} players",
+        size,
+        players
+    )]
+    Deal { players: usize, size: usize },
+    #[display(fmt = "Players tied with {} in round {} of game {}", card, round, game)]
+    Tie { game: usize, round: usize, card: u8 },
 }
 
-#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+const MAX_PLAYERS: usize = 4;
+
+#[derive(Clone, Debug)]
 struct Game {
-    decks: Vec<Deck>,
-    cards: Vec<u32>,
+    players: usize,
+    // The players' numbers at the start of the game, counting from 0, as
+    // players who run out of cards are knocked out.
+    ids: [usize; MAX_PLAYERS],
+    decks: [Deck; MAX_PLAYERS],
+    cards: [u8; MAX_PLAYERS],
 }
 
 impl Game {
     fn new(decks: impl IntoIterator<Item = impl Into<Deck>>) -> Self {
-        let mut decks = decks.into_iter().map_into().collect_vec();
-        Self {
-            cards: decks
-                .iter_mut()
-                .map(|deck| deck.0.pop_front().unwrap())
-                .collect(),
-            decks,
+        let mut game = Self {
+            players: 0,
+            ids: std::array::from_fn(|player| player),
+            decks: std::array::from_fn(|_| Deck::new()),
+            cards: [0; MAX_PLAYERS],
+        };
+        for deck in decks {
+            assert!(game.players < MAX_PLAYERS, "Too many players");
+            game.decks[game.players] = deck.into();
+            game.players += 1;
         }
+        game.draw();
+        game
     }
-}
 
-fn recursive_winner(game: &Game) -> usize {
-    let players = zip(&game.cards, &game.decks);
-    if players
-        .clone()
-        .all(|(&card, deck)| card as usize <= deck.0.len())
-    {
-        let decks = players.map(|(&card, deck)| deck.truncate(card as usize));
-        play(Game::new(decks), recursive_winner).0
-    } else {
-        game.cards
-            .iter()
-            .enumerate()
-            .max_by_key(|(_, &card)| card)
-            .unwrap()
-            .0
+    fn ids(&self) -> &[usize] {
+        &self.ids[..self.players]
+    }
+
+    fn decks(&self) -> &[Deck] {
+        &self.decks[..self.players]
+    }
+
+    fn cards(&self) -> &[u8] {
+        &self.cards[..self.players]
+    }
+
+    fn draw(&mut self) {
+        for (card, deck) in zip(&mut self.cards, &mut self.decks[..self.players]) {
+            *card = deck.pop_front().unwrap();
+        }
+    }
+
+    // Drops the players who have run out of cards, keeping the others in
+    // order.
+    fn knock_out(&mut self) {
+        let mut kept = 0;
+        for player in 0..self.players {
+            if !self.decks[player].is_empty() {
+                self.decks.swap(kept, player);
+                self.ids[kept] = self.ids[player];
+                kept += 1;
+            }
+        }
+        self.players = kept;
+    }
+
+    fn state(&self) -> u64 {
+        deck::state(self.cards(), self.decks())
+    }
+
+    // The decks as they were before the current cards were drawn.
+    fn full_decks(&self) -> Vec<Vec<u8>> {
+        zip(self.cards(), self.decks())
+            .map(|(&card, deck)| once(card).chain(deck.iter()).collect())
+            .collect()
     }
 }
 
-fn normal_winner(game: &Game) -> usize {
-    game.cards
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, c)| *c)
-        .unwrap()
-        .0
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.ids() == other.ids() && self.cards() == other.cards() && self.decks() == other.decks()
+    }
 }
 
-fn play(mut game: Game, winning_player: fn(&Game) -> usize) -> (usize, Deck) {
+fn play(
+    mut game: Game,
+    rules: &mut (impl Rules + ?Sized),
+    log: &mut Log,
+) -> Result<(usize, Deck), Error> {
+    let id = log.start();
     let mut seen = HashSet::new();
-    loop {
-        if !seen.insert(game.clone()) {
-            return (0, game.decks[0].clone());
+    for round in 1.. {
+        if !seen.insert(game.state()) {
+            if let Some(winner) = rules.repeat_winner(&game) {
+                log.repeated(id, round);
+                log.end(id, game.ids[winner], game.ids(), || game.full_decks());
+                let deck = game.full_decks().swap_remove(winner);
+                return Ok((game.ids[winner], deck.into_iter().collect()));
+            }
         }
-        let winner = winning_player(&game);
+        log.round(id, round, &game);
+        let games = log.games();
+        let winner = rules.round_winner(&game, log)?;
+        let sub_game = log.games() > games;
         let mut cards = game.cards;
-        let mut decks = game.decks;
-        cards.swap(winner, 0);
-        decks[winner].0.extend(cards);
-        if decks.iter().any(|deck| deck.0.is_empty()) {
-            return decks
-                .into_iter()
-                .find_position(|deck| !deck.0.is_empty())
-                .unwrap();
+        let cards = &mut cards[..game.players];
+        match winner {
+            Winner::Player(winner) => {
+                log.won(id, round, game.ids[winner], sub_game);
+                rules.collect(winner, cards);
+                for &card in cards.iter() {
+                    game.decks[winner].push_back(card);
+                }
+            }
+            Winner::Tied(tied) => {
+                let tie = Error::Tie {
+                    game: id,
+                    round,
+                    card: cards[tied[0]],
+                };
+                let ids = tied.iter().map(|&player| game.ids[player]).collect_vec();
+                log.tied(id, round, &ids, rules.tie());
+                match rules.tie() {
+                    Tie::Error => return Err(tie),
+                    Tie::War => {
+                        let (winner, pot) = rules::war(&mut game, tied).ok_or(tie)?;
+                        log.won(id, round, game.ids[winner], sub_game);
+                        rules.collect(winner, cards);
+                        for &card in cards.iter().chain(&pot) {
+                            game.decks[winner].push_back(card);
+                        }
+                    }
+                    Tie::Split => rules::split(&mut game, &tied, cards),
+                }
+            }
+        }
+        // The game goes on until only one player has cards left.
+        let mut holding = game.decks().iter().positions(|deck| !deck.is_empty());
+        if let (Some(winner), None) = (holding.next(), holding.next()) {
+            let winner_id = game.ids[winner];
+            log.end(id, winner_id, game.ids(), || {
+                game.decks()
+                    .iter()
+                    .map(|deck| deck.iter().collect())
+                    .collect()
+            });
+            return Ok((winner_id, game.decks[winner].clone()));
         }
-        game = Game::new(decks);
+        game.knock_out();
+        game.draw();
     }
+    unreachable!()
 }
 
-fn parse(input: &str) -> Game {
+// Cards may only be dealt more than once if ties can be resolved.
+fn parse(input: &str, tie: Tie) -> Result<Game, Error> {
     fn ws<'a, O, E: ParseError<&'a str>>(
         inner: impl FnMut(&'a str) -> IResult<&'a str, O, E>,
     ) -> impl FnMut(&'a str) -> IResult<&'a str, O, E> {
         delimited(multispace0, inner, multispace0)
     }
-    fn deck(input: &str) -> IResult<&str, Deck> {
-        preceded(
-            ws(tuple((tag("Player "), digit1, tag(":")))),
-            map(many1(ws(map_res(digit1, u32::from_str))), |cards| {
-                Deck(cards.into())
-            }),
+    fn deck(input: &str) -> IResult<&str, (usize, Vec<u8>)> {
+        pair(
+            ws(delimited(
+                tag("Player "),
+                map_res(digit1, usize::from_str),
+                tag(":"),
+            )),
+            many0(ws(map_res(digit1, u8::from_str))),
         )(input)
     }
-    let (_, game) = map(many0(deck), Game::new)(input).unwrap();
-    game
+    let (rest, decks) = ws(many0(deck))(input).map_err(|err| Error::Parse(err.to_string()))?;
+    if !rest.is_empty() {
+        return Err(Error::Parse(rest.lines().next().unwrap().to_owned()));
+    }
+    if !(1..=MAX_PLAYERS).contains(&decks.len()) {
+        return Err(Error::Players(decks.len()));
+    }
+    for (i, (player, cards)) in decks.iter().enumerate() {
+        if *player != i + 1 {
+            return Err(Error::Player {
+                expected: i + 1,
+                found: *player,
+            });
+        }
+        if cards.is_empty() {
+            return Err(Error::EmptyDeck(*player));
+        }
+    }
+    // Every card could end up in one deck.
+    let total = decks.iter().map(|(_, cards)| cards.len()).sum();
+    if total > deck::CAPACITY {
+        return Err(Error::TooManyCards(total));
+    }
+    if tie == Tie::Error {
+        if let Some(&card) = decks
+            .iter()
+            .flat_map(|(_, cards)| cards)
+            .duplicates()
+            .next()
+        {
+            return Err(Error::DuplicateCard(card));
+        }
+    }
+    Ok(Game::new(
+        decks.into_iter().map(|(_, cards)| Deck::from_iter(cards)),
+    ))
+}
+
+fn run(
+    input: &str,
+    rules: &mut impl Rules,
+    recursive: bool,
+    options: &Options,
+) -> Result<u64, Error> {
+    // Simulating and searching deal their own games, so the input isn't
+    // played at all.
+    if options.simulate.is_some() {
+        let stats = simulate::simulate(options, rules)?;
+        print!("{stats}");
+        return Ok(stats.games() as u64);
+    }
+    if let Some(target) = options.search {
+        let found = search::search(options, target, rules)?;
+        print!("{found}");
+        return Ok(found.value());
+    }
+    let mut log = if options.transcript || options.step {
+        Log::new()
+    } else {
+        Log::default()
+    };
+    let result = play(parse(input, options.tie)?, rules, &mut log);
+    if options.transcript {
+        print!("{}", transcript::render(log.events(), recursive));
+    }
+    if options.step {
+        viewer::view(log.events(), std::io::stdin().lock(), std::io::stdout());
+    }
+    let (_, deck) = result?;
+    Ok(rules.score(&deck))
 }
 
-pub(crate) fn solve(input: &str) -> u64 {
-    let (_, deck) = play(parse(input), normal_winner);
-    deck.score()
+pub(crate) fn solve_with(input: &str, options: &Options) -> Result<u64, Error> {
+    run(input, &mut Normal::new(options.tie), false, options)
 }
 
-pub(crate) fn solve_2(input: &str) -> u64 {
-    let (_, deck) = play(parse(input), recursive_winner);
-    deck.score()
+pub(crate) fn solve_2_with(input: &str, options: &Options) -> Result<u64, Error> {
+    // Play every sub-game when logging, simulating or searching, so that
+    // they're all recorded.
+    let shortcuts = !options.transcript
+        && !options.step
+        && options.simulate.is_none()
+        && options.search.is_none();
+    run(
+        input,
+        &mut Recursive::new(options.tie, shortcuts),
+        true,
+        options,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE: &str = "
+    pub(super) const EXAMPLE: &str = "
     Player 1:
         9
         2
@@ -150,18 +388,120 @@ mod tests {
     #[test]
     fn test_parse() {
         assert_eq!(
-            parse(EXAMPLE),
-            Game::new([Deck([9, 2, 6, 3, 1].into()), Deck([5, 8, 4, 7, 10].into())])
+            parse(EXAMPLE, Tie::Error),
+            Ok(Game::new([[9, 2, 6, 3, 1], [5, 8, 4, 7, 10]]))
         )
     }
 
+    #[test]
+    fn test_parse_errors() {
+        for (input, error) in [
+            ("", Error::Players(0)),
+            (
+                "Player 1: 1 Player 3: 2",
+                Error::Player {
+                    expected: 2,
+                    found: 3,
+                },
+            ),
+            ("Player 1: Player 2: 1", Error::EmptyDeck(1)),
+            ("Player 1: 1 2 Player 2: 2 3", Error::DuplicateCard(2)),
+            ("Player 1: 1 x", Error::Parse("x".to_owned())),
+            ("Player 1: 1 256", Error::Parse("256".to_owned())),
+        ] {
+            assert_eq!(parse(input, Tie::Error), Err(error));
+        }
+        assert!(parse("Player 1: 1 2 Player 2: 2 3", Tie::War).is_ok());
+        let crowded = format!("Player 1: {}", "1 ".repeat(257));
+        assert_eq!(parse(&crowded, Tie::War), Err(Error::TooManyCards(257)));
+        assert!(parse(&crowded[..crowded.len() - 2], Tie::Split).is_ok());
+    }
+
+    #[test]
+    fn test_ties() {
+        let play = |decks: [&[u8]; 2], tie| {
+            let game = Game::new(decks.map(|cards| cards.iter().copied().collect::<Deck>()));
+            play(game, &mut Normal::new(tie), &mut Log::default())
+        };
+        let tie = Error::Tie {
+            game: 1,
+            round: 1,
+            card: 5,
+        };
+        assert_eq!(play([&[5, 1], &[5, 2]], Tie::Error), Err(tie.clone()));
+        assert_eq!(
+            play([&[5, 1, 2, 3, 9], &[5, 4, 6, 7, 8]], Tie::War),
+            Ok((0, Deck::from([5, 5, 1, 2, 3, 9, 4, 6, 7, 8])))
+        );
+        assert_eq!(
+            play([&[5, 1], &[5, 2, 3, 4, 6]], Tie::War),
+            Ok((1, Deck::from([5, 5, 1, 2, 3, 4, 6])))
+        );
+        assert_eq!(play([&[5], &[5]], Tie::War), Err(tie));
+        assert_eq!(
+            play([&[5, 1], &[5, 2]], Tie::Split),
+            Ok((0, Deck::from([5, 1])))
+        );
+    }
+
+    #[test]
+    fn test_knock_out() {
+        let deck = |cards: &[u8]| cards.iter().copied().collect::<Deck>();
+        let mut log = Log::new();
+        assert_eq!(
+            play(
+                Game::new([deck(&[9, 1]), deck(&[8, 7]), deck(&[2])]),
+                &mut Normal::new(Tie::Error),
+                &mut log
+            ),
+            Ok((0, Deck::from([2, 9, 7, 8, 1])))
+        );
+        assert_eq!(log.rounds(), [4]);
+        let mut log = Log::new();
+        assert_eq!(
+            play(
+                Game::new([deck(&[1]), deck(&[9, 3]), deck(&[8, 2])]),
+                &mut Normal::new(Tie::Error),
+                &mut log
+            ),
+            Ok((1, Deck::from([9, 1, 8, 3, 2])))
+        );
+        let transcript = transcript::render(log.events(), false);
+        assert!(transcript.contains(
+            "-- Round 2 --
+Player 2's deck: 3, 9, 1, 8
+Player 3's deck: 2
+Player 2 plays: 3
+Player 3 plays: 2
+Player 2 wins the round!"
+        ));
+    }
+
     #[test]
     fn test_solve() {
-        assert_eq!(solve(EXAMPLE), 306)
+        assert_eq!(solve_with(EXAMPLE, &Options::default()), Ok(306))
     }
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2(EXAMPLE), 291)
+        assert_eq!(solve_2_with(EXAMPLE, &Options::default()), Ok(291))
+    }
+
+    #[test]
+    fn test_random_deals() {
+        // The input isn't played, so it doesn't have to be valid.
+        let options = Options {
+            simulate: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(solve_2_with("", &options), Ok(5));
+        let target = search::Target::Length;
+        let options = Options {
+            search: Some(target),
+            deck_size: 2,
+            ..Default::default()
+        };
+        let found = search::search(&options, target, &mut Recursive::new(Tie::Error, false));
+        assert_eq!(solve_2_with("", &options), Ok(found.unwrap().value()));
     }
 }
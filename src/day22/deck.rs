@@ -0,0 +1,170 @@
+use std::fmt::Debug;
+
+use itertools::Itertools;
+
+// Indices are u8s, so positions wrap around the ring for free.
+pub(crate) const CAPACITY: usize = 1 << u8::BITS;
+
+// Decks are hashed as polynomials in BASE over their cards (offset by one
+// so that no card hashes to zero), modulo 2^64.
+const BASE: u64 = 0x100000001b3;
+const POWERS: [u64; CAPACITY + 2] = {
+    let mut powers = [1u64; CAPACITY + 2];
+    let mut i = 1;
+    while i < powers.len() {
+        powers[i] = powers[i - 1].wrapping_mul(BASE);
+        i += 1;
+    }
+    powers
+};
+
+fn term(card: u8) -> u64 {
+    card as u64 + 1
+}
+
+// A fixed-capacity ring buffer of cards, which keeps its hash up to date
+// as cards are drawn and added.
+#[derive(Clone)]
+pub(crate) struct Deck {
+    cards: [u8; CAPACITY],
+    start: u8,
+    len: usize,
+    hash: u64,
+}
+
+impl Deck {
+    pub(crate) fn new() -> Self {
+        Self {
+            cards: [0; CAPACITY],
+            start: 0,
+            len: 0,
+            hash: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = u8> + Clone + '_ {
+        (0..self.len).map(|i| self.cards[self.start.wrapping_add(i as u8) as usize])
+    }
+
+    pub(crate) fn push_back(&mut self, card: u8) {
+        assert!(self.len < CAPACITY, "Deck is full");
+        self.cards[self.start.wrapping_add(self.len as u8) as usize] = card;
+        self.len += 1;
+        self.hash = self.hash.wrapping_mul(BASE).wrapping_add(term(card));
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        let card = self.cards[self.start as usize];
+        self.start = self.start.wrapping_add(1);
+        self.len -= 1;
+        self.hash = self
+            .hash
+            .wrapping_sub(term(card).wrapping_mul(POWERS[self.len]));
+        Some(card)
+    }
+
+    pub(crate) fn truncate(&self, len: usize) -> Self {
+        self.iter().take(len).collect()
+    }
+
+    pub(crate) fn score(&self) -> u64 {
+        let mut result = 0;
+        for (i, card) in self.iter().rev().enumerate() {
+            result += (i as u64 + 1) * card as u64;
+        }
+        result
+    }
+}
+
+// Hashes the sequence of cards in `decks`, each followed by a separator.
+// `cards` are the cards just drawn from the front of each deck.
+pub(crate) fn state<'a>(cards: &[u8], decks: impl IntoIterator<Item = &'a Deck>) -> u64 {
+    const SEPARATOR: u64 = CAPACITY as u64 + 1;
+    let mut result: u64 = 0;
+    for (&card, deck) in cards.iter().zip(decks) {
+        let full = term(card)
+            .wrapping_mul(POWERS[deck.len])
+            .wrapping_add(deck.hash);
+        result = result
+            .wrapping_mul(POWERS[deck.len + 2])
+            .wrapping_add(full.wrapping_mul(BASE))
+            .wrapping_add(SEPARATOR);
+    }
+    result
+}
+
+impl PartialEq for Deck {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.hash == other.hash && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Deck {}
+
+impl Debug for Deck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Deck([{}])", self.iter().join(", "))
+    }
+}
+
+impl FromIterator<u8> for Deck {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut deck = Self::new();
+        for card in iter {
+            deck.push_back(card);
+        }
+        deck
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Deck {
+    fn from(cards: [u8; N]) -> Self {
+        cards.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring() {
+        let mut deck = Deck::from([1, 2, 3]);
+        for _ in 0..1000 {
+            let card = deck.pop_front().unwrap();
+            deck.push_back(card);
+        }
+        assert_eq!(deck.iter().collect_vec(), [2, 3, 1]);
+        assert_eq!(deck, Deck::from([2, 3, 1]));
+        assert_eq!(deck.truncate(2), Deck::from([2, 3]));
+    }
+
+    #[test]
+    fn test_state() {
+        let (mut a, mut b) = (Deck::from([5, 1]), Deck::from([2, 3, 4]));
+        a.push_back(9);
+        b.push_back(8);
+        let card_a = a.pop_front().unwrap();
+        let card_b = b.pop_front().unwrap();
+        assert_eq!(
+            state(&[card_a, card_b], [&a, &b]),
+            state(&[5, 2], [&Deck::from([1, 9]), &Deck::from([3, 4, 8])])
+        );
+        // Moving a card between decks changes the state.
+        assert_ne!(
+            state(&[1, 2], [&Deck::from([3]), &Deck::from([4])]),
+            state(&[1, 2], [&Deck::new(), &Deck::from([3, 4])])
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    iter::{once, zip},
+};
+
+use itertools::Itertools;
+
+use super::{play, transcript::Log, Deck, Error, Game};
+
+// What happens when players tie with the highest card.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Tie {
+    // The game stops with an error.
+    #[default]
+    Error,
+    // The tied players each put up to three cards face down and turn
+    // another face up, until one of them wins all the cards.
+    War,
+    // The tied players each take back their own card, and share out the
+    // other players' cards in turn.
+    Split,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Winner {
+    Player(usize),
+    Tied(Vec<usize>),
+}
+
+pub(crate) trait Rules {
+    // Picks the player who wins the cards drawn in `game`.
+    fn round_winner(&mut self, game: &Game, log: &mut Log) -> Result<Winner, Error>;
+
+    fn tie(&self) -> Tie {
+        Tie::Error
+    }
+
+    // Orders the won cards, one per player, for the bottom of the winner's
+    // deck. By default the winner's card goes first.
+    fn collect(&self, winner: usize, cards: &mut [u8]) {
+        cards.swap(winner, 0);
+    }
+
+    // Picks the winner of a game whose state has been seen before, or
+    // None to play on.
+    fn repeat_winner(&self, _game: &Game) -> Option<usize> {
+        Some(0)
+    }
+
+    fn score(&self, deck: &Deck) -> u64 {
+        deck.score()
+    }
+}
+
+// Picks the player with the highest card, or every player who ties with it.
+fn highest_card(cards: impl Iterator<Item = (usize, u8)> + Clone) -> Winner {
+    let (player, highest) = cards.clone().max_by_key(|&(_, card)| card).unwrap();
+    let tied = cards.filter(|&(_, card)| card == highest);
+    if tied.clone().nth(1).is_none() {
+        Winner::Player(player)
+    } else {
+        Winner::Tied(tied.map(|(player, _)| player).collect())
+    }
+}
+
+const WAR_CARDS: usize = 3;
+
+// Plays a war between the `tied` players, returning the winner and the
+// cards they put down. Players drop out when they run out of cards, and
+// if they all do, nobody wins.
+pub(crate) fn war(game: &mut Game, mut tied: Vec<usize>) -> Option<(usize, Vec<u8>)> {
+    let mut pot = Vec::new();
+    loop {
+        let mut face_up = Vec::new();
+        for &player in &tied {
+            let deck = &mut game.decks[player];
+            for _ in 0..WAR_CARDS.min(deck.len().saturating_sub(1)) {
+                pot.push(deck.pop_front().unwrap());
+            }
+            if let Some(card) = deck.pop_front() {
+                pot.push(card);
+                face_up.push((player, card));
+            }
+        }
+        if face_up.is_empty() {
+            return None;
+        }
+        match highest_card(face_up.into_iter()) {
+            Winner::Player(player) => return Some((player, pot)),
+            Winner::Tied(players) => tied = players,
+        }
+    }
+}
+
+pub(crate) fn split(game: &mut Game, tied: &[usize], cards: &[u8]) {
+    for &player in tied {
+        game.decks[player].push_back(cards[player]);
+    }
+    let others = (0..cards.len()).filter(|player| !tied.contains(player));
+    for (&player, other) in zip(tied.iter().cycle(), others) {
+        game.decks[player].push_back(cards[other]);
+    }
+}
+
+pub(crate) struct Normal {
+    tie: Tie,
+}
+
+impl Normal {
+    pub(crate) fn new(tie: Tie) -> Self {
+        Self { tie }
+    }
+}
+
+impl Rules for Normal {
+    fn round_winner(&mut self, game: &Game, _: &mut Log) -> Result<Winner, Error> {
+        Ok(highest_card(game.cards().iter().copied().enumerate()))
+    }
+
+    fn tie(&self) -> Tie {
+        self.tie
+    }
+}
+
+pub(crate) struct Recursive {
+    tie: Tie,
+    // Whether sub-games may be decided without playing them out.
+    shortcuts: bool,
+    // Sub-game winners, keyed by the decks the sub-game starts from rather
+    // than their hash, so that a collision can't give the wrong winner.
+    winners: HashMap<Vec<Vec<u8>>, usize>,
+}
+
+impl Recursive {
+    pub(crate) fn new(tie: Tie, shortcuts: bool) -> Self {
+        Self {
+            tie,
+            shortcuts,
+            winners: HashMap::new(),
+        }
+    }
+}
+
+// Player 1 wins a game whenever they alone hold its highest card and that
+// card can't trigger a sub-game: they can never lose it (unless it's put
+// down in a war), so either the other decks run out first or the game
+// repeats.
+fn first_player_wins(game: &Game) -> bool {
+    let total: usize = game.decks().iter().map(|deck| deck.len() + 1).sum();
+    let highest = zip(game.cards(), game.decks())
+        .enumerate()
+        .flat_map(|(player, (&card, deck))| {
+            once(card)
+                .chain(deck.iter())
+                .map(move |card| (player, card))
+        })
+        .max_set_by_key(|&(_, card)| card);
+    highest.iter().all(|&(player, _)| player == 0)
+        && highest[0].1 as usize > total.saturating_sub(2)
+}
+
+impl Recursive {
+    fn sub_game_winner(&mut self, sub_game: Game, log: &mut Log) -> Result<usize, Error> {
+        if !self.shortcuts {
+            return Ok(play(sub_game, self, log)?.0);
+        }
+        if self.tie != Tie::War && first_player_wins(&sub_game) {
+            return Ok(0);
+        }
+        let key = sub_game.full_decks();
+        if let Some(&winner) = self.winners.get(&key) {
+            return Ok(winner);
+        }
+        let winner = play(sub_game, self, log)?.0;
+        self.winners.insert(key, winner);
+        Ok(winner)
+    }
+}
+
+impl Rules for Recursive {
+    fn round_winner(&mut self, game: &Game, log: &mut Log) -> Result<Winner, Error> {
+        let players = zip(game.cards(), game.decks());
+        if !players
+            .clone()
+            .all(|(&card, deck)| card as usize <= deck.len())
+        {
+            return Ok(highest_card(game.cards().iter().copied().enumerate()));
+        }
+        let sub_game = Game::new(players.map(|(&card, deck)| deck.truncate(card as usize)));
+        Ok(Winner::Player(self.sub_game_winner(sub_game, log)?))
+    }
+
+    fn tie(&self) -> Tie {
+        self.tie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, tests::EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn test_shortcuts() {
+        for input in [EXAMPLE, "Player 1: 43 19 Player 2: 2 29 14"] {
+            let game = parse(input, Tie::Error).unwrap();
+            let fast = play(
+                game.clone(),
+                &mut Recursive::new(Tie::Error, true),
+                &mut Log::default(),
+            );
+            let slow = play(
+                game,
+                &mut Recursive::new(Tie::Error, false),
+                &mut Log::default(),
+            );
+            assert_eq!(fast, slow);
+        }
+        assert!(first_player_wins(&Game::new([[4, 1], [2, 3]])));
+        assert!(!first_player_wins(&Game::new([
+            Deck::from([2, 2, 1, 1]),
+            Deck::from([1])
+        ])));
+        assert!(!first_player_wins(&Game::new([[2, 1], [4, 3]])));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        // The lowest card wins, and won cards are added in ascending order.
+        struct Lowest;
+        impl Rules for Lowest {
+            fn round_winner(&mut self, game: &Game, _: &mut Log) -> Result<Winner, Error> {
+                Ok(Winner::Player(game.cards().iter().position_min().unwrap()))
+            }
+            fn collect(&self, _: usize, cards: &mut [u8]) {
+                cards.sort();
+            }
+        }
+        let result = play(
+            Game::new([[1, 4], [2, 3]]),
+            &mut Lowest,
+            &mut Log::default(),
+        );
+        assert_eq!(result, Ok((0, Deck::from([1, 3, 2, 4]))));
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::utils::Rng;
+
+use super::{
+    play,
+    rules::Rules,
+    simulate::{check_deal, deal},
+    transcript::Log,
+    Deck, Error, Game, Options,
+};
+
+// What makes a deal extreme.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    // Rounds played, counting sub-games.
+    Length,
+    // The depth of the deepest sub-game.
+    Depth,
+    // The winner's score.
+    Score,
+    // Games, including sub-games, ended by a repeated state.
+    Repeat,
+}
+
+// Deals with at most this many cards in total are searched exhaustively.
+const EXHAUSTIVE_CARDS: usize = 8;
+
+fn measure(game: Game, rules: &mut (impl Rules + ?Sized), target: Target) -> Result<u64, Error> {
+    let mut log = Log::default();
+    let (_, deck) = play(game, rules, &mut log)?;
+    Ok(match target {
+        Target::Length => log.rounds().iter().sum::<usize>() as u64,
+        Target::Depth => log.rounds().len() as u64 - 1,
+        Target::Score => rules.score(&deck),
+        Target::Repeat => log.repeats().iter().sum::<usize>() as u64,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Found {
+    target: Target,
+    value: u64,
+    decks: Vec<Vec<u8>>,
+    // The number of deals tried.
+    tried: usize,
+}
+
+impl Found {
+    pub(crate) fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+// Prints the deal as puzzle input, so it can be saved as a fixture.
+impl Display for Found {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Best {:?} after {} deals: {}\n",
+            self.target, self.tried, self.value
+        )?;
+        for (player, deck) in self.decks.iter().enumerate() {
+            writeln!(f, "Player {}:", player + 1)?;
+            for card in deck {
+                writeln!(f, "{card}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Searches deals of `options.deck_size` cards per player for the one that
+// maximises `target`, trying every deal if there are few enough cards and
+// hill-climbing for `options.search_steps` steps otherwise. Sub-games are
+// only measured faithfully if `rules` doesn't take shortcuts.
+pub(crate) fn search(
+    options: &Options,
+    target: Target,
+    rules: &mut (impl Rules + ?Sized),
+) -> Result<Found, Error> {
+    let size = options.deck_size;
+    check_deal(options.players, size)?;
+    let mut rng = Rng::new(options.seed);
+    let mut measure = |cards: &[u8]| {
+        let decks = cards
+            .chunks(size)
+            .map(|chunk| chunk.iter().copied().collect::<Deck>());
+        measure(Game::new(decks), rules, target)
+    };
+    let total = options.players * size;
+    let mut tried = 0;
+    let best = if total <= EXHAUSTIVE_CARDS {
+        let mut permutations = (1..=total as u8).permutations(total);
+        let cards = permutations.next().unwrap();
+        let mut best = (measure(&cards)?, cards);
+        tried += 1;
+        for cards in permutations {
+            let value = measure(&cards)?;
+            if value > best.0 {
+                best = (value, cards);
+            }
+            tried += 1;
+        }
+        best
+    } else {
+        let cards = deal(&mut rng, options.players, size).full_decks().concat();
+        let mut best = (measure(&cards)?, cards);
+        tried += 1;
+        for _ in 0..options.search_steps {
+            // Swap two cards, keeping the result unless it's worse so that
+            // the search can wander across plateaus.
+            let mut cards = best.1.clone();
+            let i = rng.below(cards.len());
+            let j = rng.below(cards.len());
+            cards.swap(i, j);
+            let value = measure(&cards)?;
+            if value >= best.0 {
+                best = (value, cards);
+            }
+            tried += 1;
+        }
+        best
+    };
+    Ok(Found {
+        target,
+        value: best.0,
+        decks: best.1.chunks(size).map(<[u8]>::to_vec).collect(),
+        tried,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        parse,
+        rules::{Normal, Recursive, Tie},
+    };
+    use super::*;
+
+    fn options(deck_size: usize, search_steps: usize) -> Options {
+        Options {
+            deck_size,
+            seed: 3,
+            search_steps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        let found = search(&options(2, 0), Target::Length, &mut Normal::new(Tie::Error)).unwrap();
+        assert_eq!(found.tried, 24);
+        assert_eq!(found.value, 6);
+        let replayed = parse(found.to_string().split_once("\n\n").unwrap().1, Tie::Error);
+        assert_eq!(
+            measure(
+                replayed.unwrap(),
+                &mut Normal::new(Tie::Error),
+                Target::Length
+            ),
+            Ok(6)
+        );
+        let found = search(
+            &options(3, 0),
+            Target::Depth,
+            &mut Recursive::new(Tie::Error, false),
+        );
+        assert_eq!(found.unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_invalid_deal() {
+        let mut rules = Normal::new(Tie::Error);
+        assert_eq!(
+            search(&options(0, 0), Target::Length, &mut rules),
+            Err(Error::Deal {
+                players: 2,
+                size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_climb() {
+        let mut rules = Recursive::new(Tie::Error, false);
+        let start = search(&options(8, 0), Target::Length, &mut rules).unwrap();
+        let found = search(&options(8, 200), Target::Length, &mut rules).unwrap();
+        assert_eq!(found.tried, 201);
+        assert!(found.value > start.value);
+        assert_eq!(
+            found.decks.concat().into_iter().sorted().collect_vec(),
+            (1..=16).collect_vec()
+        );
+        let replayed = Game::new(
+            found
+                .decks
+                .iter()
+                .map(|deck| deck.iter().copied().collect::<Deck>()),
+        );
+        assert_eq!(
+            measure(replayed, &mut rules, Target::Length),
+            Ok(found.value)
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::utils::Rng;
+
+use super::{play, rules::Rules, transcript::Log, Deck, Error, Game, Options, MAX_PLAYERS};
+
+// Checks that each of `players` players can be dealt `size` cards, all
+// different.
+pub(crate) fn check_deal(players: usize, size: usize) -> Result<(), Error> {
+    if !(1..=MAX_PLAYERS).contains(&players) {
+        return Err(Error::Players(players));
+    }
+    if size == 0 || players * size > u8::MAX as usize {
+        return Err(Error::Deal { players, size });
+    }
+    Ok(())
+}
+
+// Deals `size` cards to each player from a shuffled deck of cards
+// numbered from 1.
+pub(crate) fn deal(rng: &mut Rng, players: usize, size: usize) -> Game {
+    assert_eq!(check_deal(players, size), Ok(()));
+    let mut cards = (1..=(players * size) as u8).collect_vec();
+    rng.shuffle(&mut cards);
+    Game::new(
+        cards
+            .chunks(size)
+            .map(|chunk| chunk.iter().copied().collect::<Deck>()),
+    )
+}
+
+#[derive(Debug)]
+pub(crate) struct Stats {
+    wins: Vec<usize>,
+    // Rounds played in each game, not counting sub-games.
+    lengths: Vec<usize>,
+    // The number of games whose deepest sub-game was at each depth.
+    depths: Vec<usize>,
+    repeated: usize,
+    sub_games: usize,
+    sub_repeated: usize,
+}
+
+impl Stats {
+    fn new(players: usize) -> Self {
+        Self {
+            wins: vec![0; players],
+            lengths: Vec::new(),
+            depths: Vec::new(),
+            repeated: 0,
+            sub_games: 0,
+            sub_repeated: 0,
+        }
+    }
+
+    pub(crate) fn games(&self) -> usize {
+        self.lengths.len()
+    }
+
+    fn add(&mut self, winner: usize, log: &Log) {
+        self.wins[winner] += 1;
+        self.lengths.push(log.rounds()[0]);
+        let depth = log.rounds().len() - 1;
+        if self.depths.len() <= depth {
+            self.depths.resize(depth + 1, 0);
+        }
+        self.depths[depth] += 1;
+        self.repeated += log.repeats()[0];
+        self.sub_games += log.games() - 1;
+        self.sub_repeated += log.repeats()[1..].iter().sum::<usize>();
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let games = self.games();
+        let percent = |count: usize| 100.0 * count as f64 / games as f64;
+        writeln!(f, "Games: {games}")?;
+        if games == 0 {
+            return Ok(());
+        }
+        let wins = self
+            .wins
+            .iter()
+            .enumerate()
+            .map(|(player, &wins)| format!("player {} {:.1}%", player + 1, percent(wins)));
+        writeln!(f, "Wins: {}", wins.format(", "))?;
+        let lengths = self.lengths.iter().copied().sorted().collect_vec();
+        let quantile = |q: usize| lengths[(lengths.len() - 1) * q / 100];
+        writeln!(
+            f,
+            "Rounds: min {}, 10% {}, median {}, 90% {}, max {}, mean {:.1}",
+            quantile(0),
+            quantile(10),
+            quantile(50),
+            quantile(90),
+            quantile(100),
+            lengths.iter().sum::<usize>() as f64 / games as f64
+        )?;
+        let depths = self
+            .depths
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(depth, &count)| format!("{depth} {:.1}%", percent(count)));
+        writeln!(f, "Deepest sub-game: {}", depths.format(", "))?;
+        writeln!(
+            f,
+            "Repeated states: {:.1}% of games, {} of {} sub-games",
+            percent(self.repeated),
+            self.sub_repeated,
+            self.sub_games
+        )
+    }
+}
+
+// Plays `options.simulate` random deals. Sub-games and their depths are
+// only counted faithfully if `rules` doesn't take shortcuts.
+pub(crate) fn simulate(
+    options: &Options,
+    rules: &mut (impl Rules + ?Sized),
+) -> Result<Stats, Error> {
+    check_deal(options.players, options.deck_size)?;
+    let mut rng = Rng::new(options.seed);
+    let mut stats = Stats::new(options.players);
+    for _ in 0..options.simulate.unwrap_or_default() {
+        let game = deal(&mut rng, options.players, options.deck_size);
+        let mut log = Log::default();
+        let (winner, _) = play(game, rules, &mut log)?;
+        stats.add(winner, &log);
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rules::{Normal, Recursive, Tie};
+    use super::*;
+
+    #[test]
+    fn test_deal() {
+        let game = deal(&mut Rng::new(1), 3, 5);
+        let decks = game.full_decks();
+        assert!(decks.iter().all(|deck| deck.len() == 5));
+        assert_eq!(
+            decks.concat().into_iter().sorted().collect_vec(),
+            (1..=15).collect_vec()
+        );
+    }
+
+    #[test]
+    fn test_check_deal() {
+        assert_eq!(check_deal(4, 63), Ok(()));
+        assert_eq!(check_deal(5, 1), Err(Error::Players(5)));
+        assert_eq!(check_deal(0, 1), Err(Error::Players(0)));
+        assert_eq!(
+            check_deal(2, 0),
+            Err(Error::Deal {
+                players: 2,
+                size: 0
+            })
+        );
+        assert_eq!(
+            check_deal(2, 128),
+            Err(Error::Deal {
+                players: 2,
+                size: 128
+            })
+        );
+    }
+
+    #[test]
+    fn test_simulate() {
+        let options = |seed| Options {
+            simulate: Some(50),
+            deck_size: 8,
+            seed,
+            ..Default::default()
+        };
+        let rules: [fn() -> Box<dyn Rules>; 2] = [
+            || Box::new(Normal::new(Tie::Error)),
+            || Box::new(Recursive::new(Tie::Error, false)),
+        ];
+        for rules in rules {
+            let stats = simulate(&options(7), rules().as_mut()).unwrap();
+            assert_eq!(stats.wins.iter().sum::<usize>(), 50);
+            assert_eq!(stats.depths.iter().sum::<usize>(), 50);
+            assert_eq!(
+                stats.to_string(),
+                simulate(&options(7), rules().as_mut()).unwrap().to_string()
+            );
+            assert_ne!(
+                stats.lengths,
+                simulate(&options(8), rules().as_mut()).unwrap().lengths
+            );
+        }
+        assert_eq!(
+            simulate(&options(7), &mut Normal::new(Tie::Error))
+                .unwrap()
+                .depths,
+            [50]
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use std::{fmt::Write, iter::zip};
+
+use itertools::Itertools;
+
+use super::{rules::Tie, Game};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) enum Event {
+    Start {
+        game: usize,
+        depth: usize,
+    },
+    // `decks` are as they were before the players drew `cards`. Players
+    // are numbered as they were at the start of the game.
+    Round {
+        game: usize,
+        depth: usize,
+        round: usize,
+        players: Vec<usize>,
+        decks: Vec<Vec<u8>>,
+        cards: Vec<u8>,
+    },
+    Won {
+        game: usize,
+        round: usize,
+        player: usize,
+        sub_game: bool,
+    },
+    Tied {
+        game: usize,
+        round: usize,
+        players: Vec<usize>,
+        tie: Tie,
+    },
+    Repeated {
+        game: usize,
+        round: usize,
+    },
+    End {
+        game: usize,
+        depth: usize,
+        winner: usize,
+        players: Vec<usize>,
+        decks: Vec<Vec<u8>>,
+    },
+}
+
+// Numbers games in the order they're started, counts rounds and
+// repeated-state terminations by depth, and records events if enabled.
+#[derive(Default, Debug)]
+pub(crate) struct Log {
+    enabled: bool,
+    games: usize,
+    depth: usize,
+    rounds: Vec<usize>,
+    repeats: Vec<usize>,
+    events: Vec<Event>,
+}
+
+impl Log {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub(crate) fn games(&self) -> usize {
+        self.games
+    }
+
+    pub(crate) fn rounds(&self) -> &[usize] {
+        &self.rounds
+    }
+
+    pub(crate) fn repeats(&self) -> &[usize] {
+        &self.repeats
+    }
+
+    pub(crate) fn start(&mut self) -> usize {
+        self.games += 1;
+        self.depth += 1;
+        if self.rounds.len() < self.depth {
+            self.rounds.push(0);
+            self.repeats.push(0);
+        }
+        if self.enabled {
+            self.events.push(Event::Start {
+                game: self.games,
+                depth: self.depth - 1,
+            });
+        }
+        self.games
+    }
+
+    pub(crate) fn round(&mut self, game: usize, round: usize, state: &Game) {
+        self.rounds[self.depth - 1] += 1;
+        if self.enabled {
+            self.events.push(Event::Round {
+                game,
+                depth: self.depth - 1,
+                round,
+                players: state.ids().to_vec(),
+                decks: state.full_decks(),
+                cards: state.cards().to_vec(),
+            });
+        }
+    }
+
+    pub(crate) fn won(&mut self, game: usize, round: usize, player: usize, sub_game: bool) {
+        if self.enabled {
+            self.events.push(Event::Won {
+                game,
+                round,
+                player,
+                sub_game,
+            });
+        }
+    }
+
+    pub(crate) fn tied(&mut self, game: usize, round: usize, players: &[usize], tie: Tie) {
+        if self.enabled {
+            self.events.push(Event::Tied {
+                game,
+                round,
+                players: players.to_vec(),
+                tie,
+            });
+        }
+    }
+
+    pub(crate) fn repeated(&mut self, game: usize, round: usize) {
+        self.repeats[self.depth - 1] += 1;
+        if self.enabled {
+            self.events.push(Event::Repeated { game, round });
+        }
+    }
+
+    pub(crate) fn end(
+        &mut self,
+        game: usize,
+        winner: usize,
+        players: &[usize],
+        decks: impl FnOnce() -> Vec<Vec<u8>>,
+    ) {
+        self.depth -= 1;
+        if self.enabled {
+            self.events.push(Event::End {
+                game,
+                depth: self.depth,
+                winner,
+                players: players.to_vec(),
+                decks: decks(),
+            });
+        }
+    }
+}
+
+// Renders events in the format of the puzzle's examples. The recursive
+// format numbers rounds by game and announces each game and its winner.
+pub(crate) fn render(events: &[Event], recursive: bool) -> String {
+    fn write_decks(out: &mut String, players: &[usize], decks: &[Vec<u8>]) {
+        for (player, deck) in zip(players, decks) {
+            writeln!(
+                out,
+                "Player {}'s deck: {}",
+                player + 1,
+                deck.iter().join(", ")
+            )
+            .unwrap();
+        }
+    }
+    let mut out = String::new();
+    let mut events = events.iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start { game, .. } => {
+                if recursive {
+                    writeln!(out, "=== Game {game} ===\n").unwrap();
+                }
+            }
+            Event::Round {
+                game,
+                round,
+                players,
+                decks,
+                cards,
+                ..
+            } => {
+                if recursive {
+                    writeln!(out, "-- Round {round} (Game {game}) --").unwrap();
+                } else {
+                    writeln!(out, "-- Round {round} --").unwrap();
+                }
+                write_decks(&mut out, players, decks);
+                for (player, card) in zip(players, cards) {
+                    writeln!(out, "Player {} plays: {card}", player + 1).unwrap();
+                }
+                if let Some(Event::Start { .. }) = events.peek() {
+                    writeln!(out, "Playing a sub-game to determine the winner...\n").unwrap();
+                }
+            }
+            Event::Won {
+                game,
+                round,
+                player,
+                sub_game,
+            } => {
+                if *sub_game {
+                    writeln!(out, "...anyway, back to game {game}.").unwrap();
+                }
+                if recursive {
+                    writeln!(
+                        out,
+                        "Player {} wins round {round} of game {game}!",
+                        player + 1
+                    )
+                    .unwrap();
+                } else {
+                    writeln!(out, "Player {} wins the round!", player + 1).unwrap();
+                }
+                if !matches!(events.peek(), Some(Event::End { .. })) {
+                    writeln!(out).unwrap();
+                }
+            }
+            Event::Tied { players, tie, .. } => {
+                let players = players.iter().map(|player| player + 1).join(", ");
+                match tie {
+                    Tie::Error => writeln!(out, "Players {players} tie!").unwrap(),
+                    Tie::War => writeln!(out, "Players {players} tie and go to war!").unwrap(),
+                    Tie::Split => {
+                        writeln!(out, "Players {players} tie and split the cards!").unwrap();
+                        if !matches!(events.peek(), Some(Event::End { .. })) {
+                            writeln!(out).unwrap();
+                        }
+                    }
+                }
+            }
+            Event::Repeated { game, round } => {
+                if recursive {
+                    writeln!(out, "-- Round {round} (Game {game}) --").unwrap();
+                } else {
+                    writeln!(out, "-- Round {round} --").unwrap();
+                }
+                writeln!(out, "These decks have been seen before in this game!").unwrap();
+            }
+            Event::End {
+                game,
+                depth,
+                winner,
+                players,
+                decks,
+            } => {
+                if recursive {
+                    writeln!(out, "The winner of game {game} is player {}!", winner + 1).unwrap();
+                }
+                if *depth == 0 {
+                    writeln!(out, "\n\n== Post-game results ==").unwrap();
+                    write_decks(&mut out, players, decks);
+                } else {
+                    writeln!(out).unwrap();
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        parse, play,
+        rules::{Normal, Recursive},
+        tests::EXAMPLE,
+    };
+    use super::*;
+
+    #[test]
+    fn test_normal_transcript() {
+        let mut log = Log::new();
+        play(
+            parse(EXAMPLE, Tie::Error).unwrap(),
+            &mut Normal::new(Tie::Error),
+            &mut log,
+        )
+        .unwrap();
+        let transcript = render(log.events(), false);
+        assert!(transcript.starts_with(
+            "-- Round 1 --
+Player 1's deck: 9, 2, 6, 3, 1
+Player 2's deck: 5, 8, 4, 7, 10
+Player 1 plays: 9
+Player 2 plays: 5
+Player 1 wins the round!
+
+-- Round 2 --
+"
+        ));
+        assert!(transcript.ends_with(
+            "-- Round 29 --
+Player 1's deck: 1
+Player 2's deck: 7, 3, 2, 10, 6, 8, 5, 9, 4
+Player 1 plays: 1
+Player 2 plays: 7
+Player 2 wins the round!\n\n
+== Post-game results ==
+Player 1's deck: \nPlayer 2's deck: 3, 2, 10, 6, 8, 5, 9, 4, 7, 1
+"
+        ));
+    }
+
+    #[test]
+    fn test_recursive_transcript() {
+        let mut log = Log::new();
+        let game = parse(EXAMPLE, Tie::Error).unwrap();
+        play(game, &mut Recursive::new(Tie::Error, false), &mut log).unwrap();
+        let transcript = render(log.events(), true);
+        assert!(transcript.contains(
+            "-- Round 9 (Game 1) --
+Player 1's deck: 4, 9, 8, 5, 2
+Player 2's deck: 3, 10, 1, 7, 6
+Player 1 plays: 4
+Player 2 plays: 3
+Playing a sub-game to determine the winner...
+
+=== Game 2 ===
+
+-- Round 1 (Game 2) --
+Player 1's deck: 9, 8, 5, 2
+Player 2's deck: 10, 1, 7
+"
+        ));
+        assert!(transcript.contains(
+            "The winner of game 2 is player 2!
+
+...anyway, back to game 1.
+Player 2 wins round 9 of game 1!
+
+-- Round 10 (Game 1) --
+"
+        ));
+        assert!(transcript.ends_with(
+            "Player 2 wins round 17 of game 1!
+The winner of game 1 is player 2!\n\n
+== Post-game results ==
+Player 1's deck: \nPlayer 2's deck: 7, 5, 6, 2, 4, 1, 10, 8, 9, 3
+"
+        ));
+    }
+
+    #[test]
+    fn test_repeated_transcript() {
+        let mut log = Log::new();
+        let game = parse("Player 1: 43 19 Player 2: 2 29 14", Tie::Error).unwrap();
+        play(game, &mut Recursive::new(Tie::Error, false), &mut log).unwrap();
+        assert!(log
+            .events()
+            .contains(&Event::Repeated { game: 1, round: 7 }));
+        assert!(render(log.events(), true).ends_with(
+            "These decks have been seen before in this game!
+The winner of game 1 is player 1!
+
+
+== Post-game results ==
+Player 1's deck: 43, 19
+Player 2's deck: 2, 29, 14
+"
+        ));
+    }
+}
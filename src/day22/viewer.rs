@@ -0,0 +1,307 @@
+use std::{
+    fmt::Write as _,
+    io::{BufRead, Write},
+    iter::zip,
+};
+
+use itertools::Itertools;
+
+use super::transcript::Event;
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const HIGHLIGHT: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    // To the next round of the current game, or its end.
+    Next,
+    // To the next round of any game, entering sub-games.
+    Into,
+    // To the end of the current game.
+    Out,
+    Back,
+    Quit,
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "" | "n" => Some(Self::Next),
+            "i" => Some(Self::Into),
+            "o" => Some(Self::Out),
+            "b" => Some(Self::Back),
+            "q" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+// Steps through a recorded game. Positions are the indices of events
+// worth stopping at: rounds, repeated states and the ends of games.
+pub(crate) struct Viewer<'a> {
+    events: &'a [Event],
+    depths: Vec<usize>,
+    position: usize,
+    history: Vec<usize>,
+}
+
+impl<'a> Viewer<'a> {
+    pub(crate) fn new(events: &'a [Event]) -> Self {
+        let mut depths = Vec::with_capacity(events.len());
+        let mut depth = 0;
+        for event in events {
+            if let Event::Start { depth: start, .. } = event {
+                depth = *start;
+            }
+            depths.push(depth);
+            if let Event::End { depth: end, .. } = event {
+                depth = end.saturating_sub(1);
+            }
+        }
+        let mut viewer = Self {
+            events,
+            depths,
+            position: 0,
+            history: Vec::new(),
+        };
+        viewer.position = viewer.next_stop(0, usize::MAX).unwrap_or_default();
+        viewer
+    }
+
+    fn is_stop(&self, i: usize) -> bool {
+        matches!(
+            self.events[i],
+            Event::Round { .. } | Event::Repeated { .. } | Event::End { .. }
+        )
+    }
+
+    fn next_stop(&self, from: usize, max_depth: usize) -> Option<usize> {
+        (from..self.events.len()).find(|&i| self.is_stop(i) && self.depths[i] <= max_depth)
+    }
+
+    // Moves as `command` says, returning false once there's nowhere to go.
+    pub(crate) fn step(&mut self, command: Command) -> bool {
+        let depth = self.depths[self.position];
+        let next = match command {
+            Command::Next => self.next_stop(self.position + 1, depth),
+            Command::Into => self.next_stop(self.position + 1, usize::MAX),
+            Command::Out => (self.position..self.events.len())
+                .find(|&i| matches!(self.events[i], Event::End { .. }) && self.depths[i] == depth),
+            Command::Back => {
+                if let Some(previous) = self.history.pop() {
+                    self.position = previous;
+                }
+                return true;
+            }
+            Command::Quit => None,
+        };
+        match next {
+            Some(next) => {
+                self.history.push(self.position);
+                self.position = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The games, and their rounds, leading to the current position.
+    fn path(&self) -> String {
+        let mut path = Vec::new();
+        for event in &self.events[..=self.position] {
+            match event {
+                Event::Start { .. } => path.push(None),
+                Event::Round { game, round, .. } => *path.last_mut().unwrap() = Some((game, round)),
+                Event::End { .. } => {
+                    path.pop();
+                }
+                _ => (),
+            }
+        }
+        path.into_iter()
+            .flatten()
+            .map(|(game, round)| format!("game {game} round {round}"))
+            .join(" > ")
+    }
+
+    fn outcome(&self, game: usize, round: usize) -> Option<String> {
+        self.events[self.position..]
+            .iter()
+            .find_map(|event| match event {
+                Event::Won {
+                    game: g,
+                    round: r,
+                    player,
+                    sub_game,
+                } if (*g, *r) == (game, round) => Some(if *sub_game {
+                    format!(
+                        "Player {} wins the round by winning a sub-game.",
+                        player + 1
+                    )
+                } else {
+                    format!("Player {} wins the round.", player + 1)
+                }),
+                Event::Tied {
+                    game: g,
+                    round: r,
+                    players,
+                    ..
+                } if (*g, *r) == (game, round) => {
+                    let players = players.iter().map(|player| player + 1).join(", ");
+                    Some(format!("Players {players} tie."))
+                }
+                _ => None,
+            })
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let mut out = String::new();
+        let depth = self.depths[self.position];
+        match &self.events[self.position] {
+            Event::Round {
+                game,
+                round,
+                players,
+                decks,
+                cards,
+                ..
+            } => {
+                writeln!(out, "Game {game}, round {round} (depth {depth})").unwrap();
+                writeln!(out, "{}\n", self.path()).unwrap();
+                for (player, (deck, card)) in zip(players, zip(decks, cards)) {
+                    let deck = deck.iter().skip(1).join(", ");
+                    writeln!(out, "Player {}: [{card}] {deck}", player + 1).unwrap();
+                }
+                writeln!(out).unwrap();
+                let outcome = self.outcome(*game, *round);
+                if let Some(outcome) = outcome {
+                    writeln!(out, "{outcome}").unwrap();
+                }
+            }
+            Event::Repeated { game, round } => {
+                writeln!(out, "Game {game}, round {round} (depth {depth})").unwrap();
+                writeln!(out, "{}\n", self.path()).unwrap();
+                writeln!(
+                    out,
+                    "{HIGHLIGHT}These decks have been seen before in this game!{RESET}"
+                )
+                .unwrap();
+            }
+            Event::End {
+                game,
+                winner,
+                players,
+                decks,
+                ..
+            } => {
+                writeln!(out, "End of game {game} (depth {depth})\n").unwrap();
+                for (player, deck) in zip(players, decks) {
+                    writeln!(out, "Player {}: {}", player + 1, deck.iter().join(", ")).unwrap();
+                }
+                writeln!(out, "\nPlayer {} wins game {game}.", winner + 1).unwrap();
+            }
+            _ => unreachable!(),
+        }
+        out
+    }
+}
+
+// Reads commands from `input` until it ends or the game does.
+pub(crate) fn view(events: &[Event], input: impl BufRead, mut output: impl Write) {
+    if events.is_empty() {
+        return;
+    }
+    let mut viewer = Viewer::new(events);
+    let mut lines = input.lines();
+    loop {
+        write!(
+            output,
+            "{CLEAR}{}\n[enter/n] next round  [i] into sub-games  [o] out of this game  [b] back  [q] quit\n> ",
+            viewer.render()
+        )
+        .unwrap();
+        output.flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        if let Some(command) = Command::parse(&line) {
+            if !viewer.step(command) {
+                break;
+            }
+        }
+    }
+    writeln!(output).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        parse, play,
+        rules::{Recursive, Tie},
+        tests::EXAMPLE,
+        transcript::Log,
+    };
+    use super::*;
+
+    fn example_log() -> Log {
+        let mut log = Log::new();
+        let game = parse(EXAMPLE, Tie::Error).unwrap();
+        play(game, &mut Recursive::new(Tie::Error, false), &mut log).unwrap();
+        log
+    }
+
+    #[test]
+    fn test_steps() {
+        let log = example_log();
+        let mut viewer = Viewer::new(log.events());
+        assert!(viewer.render().starts_with("Game 1, round 1 (depth 0)"));
+        for _ in 0..8 {
+            assert!(viewer.step(Command::Next));
+        }
+        let round_9 = viewer.render();
+        assert!(round_9.starts_with("Game 1, round 9 (depth 0)"));
+        assert!(round_9.contains("Player 1: [4] 9, 8, 5, 2"));
+        assert!(round_9.contains("Player 2 wins the round by winning a sub-game."));
+        assert!(viewer.step(Command::Into));
+        assert!(viewer.render().starts_with("Game 2, round 1 (depth 1)"));
+        assert!(viewer.render().contains("game 1 round 9 > game 2 round 1"));
+        assert!(viewer.step(Command::Out));
+        assert!(viewer.render().starts_with("End of game 2 (depth 1)"));
+        assert!(viewer.step(Command::Next));
+        assert!(viewer.render().starts_with("Game 1, round 10 (depth 0)"));
+        assert!(viewer.step(Command::Back));
+        assert!(viewer.step(Command::Back));
+        assert!(viewer.render().starts_with("Game 2, round 1 (depth 1)"));
+        assert!(viewer.step(Command::Out));
+        assert!(viewer.step(Command::Next));
+        assert!(viewer.step(Command::Out));
+        assert!(viewer.render().contains("Player 2 wins game 1."));
+        assert!(!viewer.step(Command::Next));
+        assert!(!viewer.step(Command::Quit));
+    }
+
+    #[test]
+    fn test_repeated() {
+        let mut log = Log::new();
+        let game = parse("Player 1: 43 19 Player 2: 2 29 14", Tie::Error).unwrap();
+        play(game, &mut Recursive::new(Tie::Error, false), &mut log).unwrap();
+        let mut viewer = Viewer::new(log.events());
+        while !viewer.render().contains("seen before") {
+            assert!(viewer.step(Command::Next));
+        }
+        assert!(viewer.render().starts_with("Game 1, round 7"));
+        assert!(viewer.render().contains(HIGHLIGHT));
+    }
+
+    #[test]
+    fn test_view() {
+        let log = example_log();
+        let mut output = Vec::new();
+        view(log.events(), "n\ni\nx\nq\n".as_bytes(), &mut output);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches(CLEAR).count(), 4);
+        assert!(output.contains("Game 1, round 2 (depth 0)"));
+        assert!(output.contains("Game 1, round 3 (depth 0)"));
+    }
+}
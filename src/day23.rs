@@ -1,170 +1,495 @@
 use std::{
-    cell::RefCell,
     fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
     iter::successors,
-    rc::{Rc, Weak},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
 };
 
+use derive_more::Display;
 use itertools::Itertools;
 
-#[derive(Debug)]
-struct Cup {
-    dest: Weak<RefCell<Cup>>,
-    next: Weak<RefCell<Cup>>,
-    value: u32,
+mod snapshot;
+mod trace;
+
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// The number of cups picked up each move
+    #[arg(long, default_value_t = 3)]
+    pick_up: usize,
+    /// Which way labels are counted to find the destination cup
+    #[arg(long, value_enum, default_value_t = Direction::Lower)]
+    destination: Direction,
+    /// The number of cups the current cup moves clockwise after each move
+    #[arg(long, default_value_t = 1)]
+    advance: usize,
+    /// Play this many moves instead of the puzzle's
+    #[arg(long)]
+    moves: Option<usize>,
+    /// Skip ahead by whole cycles once an arrangement repeats, and print
+    /// the cycle length
+    #[arg(long, conflicts_with = "trace")]
+    cycles: bool,
+    /// Print each move like the puzzle's example, or for games of more
+    /// than 20 cups, a summary line every --trace-every moves
+    #[arg(long)]
+    trace: bool,
+    /// How often to summarise moves in the trace of a large game
+    #[arg(long, default_value_t = NonZeroUsize::new(1_000_000).unwrap(), value_name = "MOVES")]
+    trace_every: NonZeroUsize,
+    /// Start from a snapshot saved by --save instead of the input
+    #[arg(long, value_name = "FILE")]
+    restore: Option<PathBuf>,
+    /// Save a snapshot of the cups after the last move, which --restore
+    /// resumes from
+    #[arg(long, value_name = "FILE")]
+    save: Option<PathBuf>,
+    /// Also save the snapshot every this many moves, as a checkpoint
+    #[arg(
+        long,
+        value_name = "MOVES",
+        requires = "save",
+        conflicts_with_all = ["trace", "cycles"]
+    )]
+    save_every: Option<NonZeroUsize>,
+    /// Print the cups following this one after the last move
+    #[arg(long, value_name = "LABEL")]
+    after: Option<u32>,
+    /// How many cups --after prints
+    #[arg(long, default_value_t = 8, value_name = "CUPS")]
+    after_count: usize,
+    /// Print how far clockwise of the current cup this one is after the
+    /// last move
+    #[arg(long, value_name = "LABEL")]
+    position: Option<u32>,
+    /// Print every cup, clockwise from the current one, after the last move
+    #[arg(long)]
+    arrangement: bool,
 }
 
-impl PartialEq for Cup {
-    fn eq(&self, other: &Self) -> bool {
-        self.value == other.value
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            pick_up: 3,
+            destination: Direction::Lower,
+            advance: 1,
+            moves: None,
+            cycles: false,
+            trace: false,
+            trace_every: NonZeroUsize::new(1_000_000).unwrap(),
+            restore: None,
+            save: None,
+            save_every: None,
+            after: None,
+            after_count: 8,
+            position: None,
+            arrangement: false,
+        }
     }
 }
 
-fn next_cups(cup: Rc<RefCell<Cup>>) -> impl Iterator<Item = Rc<RefCell<Cup>>> {
-    successors(Some(cup), |cup| cup.borrow().next.upgrade())
+#[derive(Debug, PartialEq, Eq, Display)]
+pub(crate) enum Error {
+    #[display(fmt = "Invalid cup label {:?}", _0)]
+    Label(String),
+    #[display(fmt = "There are no cups")]
+    Empty,
+    #[display(fmt = "Cup label {} is outside 1 to {}", label, cups)]
+    OutOfRange { label: u32, cups: usize },
+    #[display(fmt = "Cup label {} appears more than once", _0)]
+    Repeated(u32),
+    #[display(fmt = "Can't pick up {} of {} cups", pick_up, cups)]
+    PickUp { pick_up: usize, cups: usize },
+    #[display(fmt = "Invalid snapshot: {}", _0)]
+    Snapshot(String),
+    #[display(fmt = "{}", _0)]
+    Io(String),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    // The next lower label, wrapping around to the highest.
+    Lower,
+    // The next higher label, wrapping around to the lowest.
+    Higher,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rules {
+    pick_up: usize,
+    destination: Direction,
+    advance: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::from(&Options::default())
+    }
+}
+
+impl From<&Options> for Rules {
+    fn from(options: &Options) -> Self {
+        Self {
+            pick_up: options.pick_up,
+            destination: options.destination,
+            advance: options.advance,
+        }
+    }
 }
 
-fn dest_cups(cup: Rc<RefCell<Cup>>) -> impl Iterator<Item = Rc<RefCell<Cup>>> {
-    successors(Some(cup), |cup| cup.borrow().dest.upgrade())
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[display(
+    fmt = "The arrangement after move {} repeats every {} moves",
+    start,
+    length
+)]
+struct Cycle {
+    start: usize,
+    length: usize,
 }
 
-#[derive(Debug)]
+// The circle of cups as a linked list over labels: `next[label]` is the
+// label of the cup clockwise of `label`. Labels run from 1 to the number
+// of cups, so index 0 is unused.
+#[derive(Clone, Debug)]
 struct Cups {
-    size: usize,
-    first: Rc<RefCell<Cup>>,
-    current: Rc<RefCell<Cup>>,
-    cups: Vec<Rc<RefCell<Cup>>>,
+    next: Vec<u32>,
+    // The first cup in the input, where Display starts.
+    first: u32,
+    current: u32,
+    // The moves played so far, including any before a snapshot was saved.
+    moves: usize,
+    rules: Rules,
 }
 
 impl Cups {
     fn new(cups: Vec<u32>) -> Self {
-        let cups = cups
-            .into_iter()
-            .map(|value| {
-                Rc::new(RefCell::new(Cup {
-                    dest: Weak::new(),
-                    next: Weak::new(),
-                    value,
-                }))
-            })
-            .collect_vec();
-        for (prev, next) in cups.iter().circular_tuple_windows() {
-            prev.borrow_mut().next = Rc::downgrade(next);
-        }
-        let mut sorted_cups = cups.clone();
-        sorted_cups.sort_by_key(|cup| cup.borrow().value);
-        for (prev, next) in sorted_cups.iter().circular_tuple_windows() {
-            next.borrow_mut().dest = Rc::downgrade(prev);
+        let mut next = vec![0; cups.len() + 1];
+        for (&prev, &cup) in cups.iter().circular_tuple_windows() {
+            next[prev as usize] = cup;
         }
         Self {
-            size: cups.len(),
-            first: Rc::clone(&sorted_cups[0]),
-            current: Rc::clone(&cups[0]),
-            cups,
+            next,
+            first: cups[0],
+            current: cups[0],
+            moves: 0,
+            rules: Rules::default(),
         }
     }
 
-    fn value(&self) -> String {
-        let mut result = String::new();
-        for cup in next_cups(self.first.clone()).skip(1).take(self.size - 1) {
-            result += &cup.borrow().value.to_string();
+    fn with_rules(mut self, rules: Rules) -> Result<Self, Error> {
+        if !(1..self.size()).contains(&rules.pick_up) {
+            return Err(Error::PickUp {
+                pick_up: rules.pick_up,
+                cups: self.size(),
+            });
+        }
+        self.rules = rules;
+        Ok(self)
+    }
+
+    fn size(&self) -> usize {
+        self.next.len() - 1
+    }
+
+    // The labels clockwise from `label`, starting with it, forever.
+    fn next_cups(&self, label: u32) -> impl Iterator<Item = u32> + Clone + '_ {
+        successors(Some(label), |&cup| Some(self.next[cup as usize]))
+    }
+
+    fn check_label(&self, label: u32) -> Result<(), Error> {
+        if (1..=self.size() as u32).contains(&label) {
+            Ok(())
+        } else {
+            Err(Error::OutOfRange {
+                label,
+                cups: self.size(),
+            })
         }
-        result
+    }
+
+    // The `count` labels clockwise of `label`, wrapping around the circle
+    // if there are fewer cups.
+    fn after(&self, label: u32, count: usize) -> Result<impl Iterator<Item = u32> + '_, Error> {
+        self.check_label(label)?;
+        Ok(self.next_cups(label).skip(1).take(count))
+    }
+
+    // The number of places `label` is clockwise of the current cup.
+    fn position(&self, label: u32) -> Result<usize, Error> {
+        self.check_label(label)?;
+        Ok(self.arrangement().position(|cup| cup == label).unwrap())
+    }
+
+    // Every label, clockwise from the current cup.
+    fn arrangement(&self) -> impl Iterator<Item = u32> + '_ {
+        self.next_cups(self.current).take(self.size())
+    }
+
+    // The labels after cup 1, separated by commas if any have more than
+    // one digit.
+    fn value(&self) -> String {
+        let separator = if self.size() > 9 { "," } else { "" };
+        self.next_cups(1)
+            .skip(1)
+            .take(self.size() - 1)
+            .join(separator)
     }
 
     fn product(&self) -> u64 {
-        next_cups(self.first.clone())
+        self.next_cups(1)
             .skip(1)
             .take(2)
-            .map(|cup| cup.borrow().value as u64)
+            .map(|cup| cup as u64)
             .product()
     }
 
-    fn iterate(&mut self) {
-        let cups = self.pick_up(3);
-        let destination = self.get_destination(cups.clone());
-        self.put_down(destination, cups);
-        let new_current = self.current.borrow().next.upgrade().unwrap();
-        self.current = new_current;
+    // Plays a move, returning the destination cup.
+    fn iterate(&mut self) -> u32 {
+        let count = self.rules.pick_up;
+        let cups = self.pick_up(count);
+        let destination = self.get_destination(cups, count);
+        self.put_down(destination, cups, count);
+        let current = self.next_cups(self.current).nth(self.rules.advance);
+        self.current = current.unwrap();
+        self.moves += 1;
+        destination
     }
 
-    fn get_destination(&self, holding_cup: Rc<RefCell<Cup>>) -> Rc<RefCell<Cup>> {
-        let avoid_cups = next_cups(holding_cup).collect_vec();
-        for dest in dest_cups(self.current.clone()).skip(1) {
-            if avoid_cups.contains(&dest) {
-                continue;
+    // Whether both circles read the same clockwise from the current cup,
+    // which is all that decides the moves to come.
+    fn same_arrangement(&self, other: &Cups) -> bool {
+        self.arrangement().eq(other.arrangement())
+    }
+
+    // Plays `moves` moves, skipping whole cycles once an arrangement
+    // repeats. Brent's algorithm finds the cycle by comparing a copy of
+    // the cups that runs ahead with one that jumps to it at every power of
+    // two, so that only arrangements that really match count as a repeat
+    // and nothing but the copies is kept.
+    fn play_cycles(&mut self, moves: usize) -> Option<Cycle> {
+        if self.moves >= moves {
+            return None;
+        }
+        let mut tortoise = self.clone();
+        let mut hare = self.clone();
+        hare.iterate();
+        let (mut power, mut length) = (1, 1);
+        while !tortoise.same_arrangement(&hare) {
+            if hare.moves == moves {
+                *self = hare;
+                return None;
+            }
+            if power == length {
+                tortoise = hare.clone();
+                power *= 2;
+                length = 0;
             }
-            return dest;
+            hare.iterate();
+            length += 1;
+        }
+        // Set off again `length` moves apart: the first time the two match
+        // is where the cycle starts.
+        let mut tortoise = self.clone();
+        let mut hare = self.clone();
+        for _ in 0..length {
+            hare.iterate();
+        }
+        while !tortoise.same_arrangement(&hare) {
+            tortoise.iterate();
+            hare.iterate();
+        }
+        for _ in 0..(moves - hare.moves) % length {
+            hare.iterate();
         }
-        unreachable!()
+        hare.moves = moves;
+        *self = hare;
+        Some(Cycle {
+            start: tortoise.moves,
+            length,
+        })
     }
 
-    fn pick_up(&mut self, count: usize) -> Rc<RefCell<Cup>> {
-        let first = next_cups(self.current.clone()).skip(1).next().unwrap();
-        let last = next_cups(first.clone()).skip(count - 1).next().unwrap();
-        self.current.borrow_mut().next = last.borrow().next.clone();
-        last.borrow_mut().next = Weak::new(); // Break the cycle
+    fn get_destination(&self, holding_cup: u32, count: usize) -> u32 {
+        let size = self.size() as u32;
+        let avoid_cups = self.next_cups(holding_cup).take(count);
+        let step = match self.rules.destination {
+            Direction::Lower => |cup, size| if cup == 1 { size } else { cup - 1 },
+            Direction::Higher => |cup, size| if cup == size { 1 } else { cup + 1 },
+        };
+        successors(Some(self.current), |&cup| Some(step(cup, size)))
+            .skip(1)
+            .find(|&cup| !avoid_cups.clone().contains(&cup))
+            .unwrap()
+    }
+
+    // Unlinks the `count` cups after the current one, returning the first.
+    // They stay linked to each other.
+    fn pick_up(&mut self, count: usize) -> u32 {
+        let first = self.next[self.current as usize];
+        let last = self.next_cups(first).nth(count - 1).unwrap();
+        self.next[self.current as usize] = self.next[last as usize];
         first
     }
 
-    fn put_down(&mut self, dest: Rc<RefCell<Cup>>, cup: Rc<RefCell<Cup>>) {
-        next_cups(cup.clone()).last().unwrap().borrow_mut().next = dest.borrow().next.clone();
-        dest.borrow_mut().next = Rc::downgrade(&cup);
+    fn put_down(&mut self, dest: u32, cup: u32, count: usize) {
+        let last = self.next_cups(cup).nth(count - 1).unwrap();
+        self.next[last as usize] = self.next[dest as usize];
+        self.next[dest as usize] = cup;
     }
 }
 
 impl Display for Cups {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut parts = Vec::new();
-        for cup in next_cups(self.cups[0].clone()).take(self.size) {
+        for cup in self.next_cups(self.first).take(self.size()) {
             if cup == self.current {
-                parts.push(format!("({})", cup.borrow().value));
+                parts.push(format!("({})", cup));
             } else {
-                parts.push(format!("{}", cup.borrow().value));
+                parts.push(format!("{}", cup));
             }
         }
         write!(f, "{}", parts.join(" "))
     }
 }
 
+// Labels are either single digits, as in the puzzle, or separated by
+// commas or whitespace.
+fn parse(input: &str) -> Result<Vec<u32>, Error> {
+    let input = input.trim();
+    let is_separator = |c: char| c == ',' || c.is_whitespace();
+    let labels: Vec<u32> = if input.contains(is_separator) {
+        input
+            .split(is_separator)
+            .filter(|label| !label.is_empty())
+            .map(|label| label.parse().map_err(|_| Error::Label(label.to_owned())))
+            .try_collect()?
+    } else {
+        input
+            .chars()
+            .map(|c| c.to_digit(10).ok_or_else(|| Error::Label(c.to_string())))
+            .try_collect()?
+    };
+    check(&labels)?;
+    Ok(labels)
+}
+
+fn check(labels: &[u32]) -> Result<(), Error> {
+    if labels.is_empty() {
+        return Err(Error::Empty);
+    }
+    // Labels index the successor array, so they must be 1 to n.
+    let mut seen = vec![false; labels.len() + 1];
+    for &label in labels {
+        if !(1..=labels.len() as u32).contains(&label) {
+            return Err(Error::OutOfRange {
+                label,
+                cups: labels.len(),
+            });
+        }
+        if std::mem::replace(&mut seen[label as usize], true) {
+            return Err(Error::Repeated(label));
+        }
+    }
+    Ok(())
+}
+
 impl TryFrom<(&str, Option<usize>)> for Cups {
-    type Error = ();
+    type Error = Error;
     fn try_from((value, extend): (&str, Option<usize>)) -> Result<Self, Self::Error> {
-        let mut cups = Vec::new();
-        for c in value.chars() {
-            let digit = c.to_digit(10).ok_or(())?;
-            cups.push(digit);
-        }
+        let mut cups = parse(value)?;
         if let Some(extend) = extend {
-            cups.reserve(extend - cups.len());
-            for _ in cups.len()..extend {
-                cups.push(1 + cups.len() as u32);
-            }
+            let max = cups.iter().copied().max().unwrap();
+            cups.extend((max + 1..).take(extend.saturating_sub(cups.len())));
         }
         Ok(Cups::new(cups))
     }
 }
 
-fn compute(input: &str, extend: Option<usize>, iterations: usize) -> Cups {
-    let mut cups: Cups = (input, extend).try_into().unwrap();
-    for _ in 0..iterations {
-        cups.iterate();
+fn run(input: &str, extend: Option<usize>, moves: usize, options: &Options) -> Result<Cups, Error> {
+    fn io_error(path: &Path) -> impl Fn(io::Error) -> Error + '_ {
+        move |err| Error::Io(format!("{}: {err}", path.display()))
     }
-    cups
+    // Writes a new snapshot beside the old one before replacing it, so an
+    // interruption can't leave a partial snapshot behind.
+    fn save(cups: &Cups, path: &Path) -> Result<(), Error> {
+        let partial = path.with_extension("partial");
+        let file = File::create(&partial).map_err(io_error(&partial))?;
+        cups.save(BufWriter::new(file))
+            .map_err(io_error(&partial))?;
+        fs::rename(&partial, path).map_err(io_error(path))
+    }
+    let moves = options.moves.unwrap_or(moves);
+    let cups = match &options.restore {
+        Some(path) => Cups::restore(BufReader::new(File::open(path).map_err(io_error(path))?))?,
+        None => Cups::try_from((input, extend))?,
+    };
+    let mut cups = cups.with_rules(options.into())?;
+    if options.trace {
+        trace::trace(&mut cups, moves, options.trace_every, io::stdout().lock())
+            .map_err(|err| Error::Io(err.to_string()))?;
+    } else if options.cycles {
+        match cups.play_cycles(moves) {
+            Some(cycle) => println!("{cycle}"),
+            None => println!("Found no repeating arrangement within {moves} moves"),
+        }
+    } else {
+        while cups.moves < moves {
+            cups.iterate();
+            if let (Some(path), Some(every)) = (&options.save, options.save_every) {
+                if cups.moves % every == 0 {
+                    save(&cups, path)?;
+                }
+            }
+        }
+    }
+    if let Some(path) = &options.save {
+        save(&cups, path)?;
+    }
+    if let Some(label) = options.after {
+        let after = cups.after(label, options.after_count)?.join(", ");
+        println!("After cup {label}: {after}");
+    }
+    if let Some(label) = options.position {
+        let position = cups.position(label)?;
+        println!("Cup {label} is {position} places clockwise of the current cup");
+    }
+    if options.arrangement {
+        println!(
+            "Cups from the current one: {}",
+            cups.arrangement().join(", ")
+        );
+    }
+    Ok(cups)
 }
 
-pub(crate) fn solve(input: &str) -> String {
-    compute(input, None, 100).value()
+pub(crate) fn solve_with(input: &str, options: &Options) -> Result<String, Error> {
+    Ok(run(input, None, 100, options)?.value())
 }
 
-pub(crate) fn solve_2(input: &str) -> u64 {
-    compute(input, Some(1_000_000), 10_000_000).product()
+pub(crate) fn solve_2_with(input: &str, options: &Options) -> Result<u64, Error> {
+    Ok(run(input, Some(1_000_000), 10_000_000, options)?.product())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn compute(
+        input: &str,
+        extend: Option<usize>,
+        iterations: usize,
+        rules: Rules,
+    ) -> Result<Cups, Error> {
+        let mut cups = Cups::try_from((input, extend))?.with_rules(rules)?;
+        for _ in 0..iterations {
+            cups.iterate();
+        }
+        Ok(cups)
+    }
+
     #[test]
     fn test_iterate() {
         let mut cups: Cups = ("389125467", None).try_into().unwrap();
@@ -174,20 +499,165 @@ mod tests {
 
     #[test]
     fn test_example() {
-        assert_eq!(compute("389125467", None, 10).value(), "92658374");
-        assert_eq!(compute("389125467", None, 100).value(), "67384529");
+        assert_eq!(
+            compute("389125467", None, 10, Rules::default())
+                .unwrap()
+                .value(),
+            "92658374"
+        );
+        assert_eq!(
+            compute("389125467", None, 100, Rules::default())
+                .unwrap()
+                .value(),
+            "67384529"
+        );
     }
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2("389125467"), 149245887792);
+        assert_eq!(
+            solve_2_with("389125467", &Options::default()),
+            Ok(149245887792)
+        );
     }
 
     #[test]
     fn test_extend() {
         assert_eq!(
-            compute("12345", None, 10).value(),
-            compute("1", Some(5), 10).value()
+            compute("12345", None, 10, Rules::default())
+                .unwrap()
+                .value(),
+            compute("1", Some(5), 10, Rules::default()).unwrap().value()
+        );
+        assert_eq!(
+            compute("3 1 2 4 5 6 7 8 9 10 11 12", None, 10, Rules::default())
+                .unwrap()
+                .value(),
+            compute("312", Some(12), 10, Rules::default())
+                .unwrap()
+                .value()
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let cups = || Cups::try_from(("389125467", None)).unwrap();
+        let mut skipped = cups();
+        let cycle = skipped.play_cycles(100_000).unwrap();
+        let mut played = cups();
+        for _ in 0..100_000 {
+            played.iterate();
+        }
+        assert_eq!(skipped.to_string(), played.to_string());
+        // The cycle brings back the arrangement it started from, and
+        // nothing shorter does.
+        let mut cycling = cups();
+        for _ in 0..cycle.start {
+            cycling.iterate();
+        }
+        let start = cycling.clone();
+        for i in 1..=cycle.length {
+            cycling.iterate();
+            assert_eq!(cycling.same_arrangement(&start), i == cycle.length);
+        }
+        let mut long = cups();
+        assert_eq!(long.play_cycles(1_000_000_000_000), Some(cycle));
+        assert_eq!(cups().play_cycles(cycle.start), None);
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("day23-checkpoints-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cups.bin");
+        let options = |moves, restore| Options {
+            moves: Some(moves),
+            save: Some(path.clone()),
+            save_every: NonZeroUsize::new(20),
+            restore,
+            ..Default::default()
+        };
+        let expected = compute("389125467", None, 100, Rules::default()).unwrap();
+        // Stop part way, as if interrupted, then carry on from the snapshot.
+        run("389125467", None, 0, &options(50, None)).unwrap();
+        let resumed = run("", None, 0, &options(100, Some(path.clone()))).unwrap();
+        assert_eq!(resumed.moves, 100);
+        assert_eq!(resumed.to_string(), expected.to_string());
+        let saved = Cups::restore(File::open(&path).unwrap()).unwrap();
+        assert_eq!(saved.to_string(), expected.to_string());
+        assert!(!path.with_extension("partial").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_queries() {
+        let cups = compute("389125467", None, 10, Rules::default()).unwrap();
+        assert_eq!(cups.to_string(), "3 7 4 1 9 2 6 5 (8)");
+        assert_eq!(cups.after(1, 3).unwrap().collect_vec(), [9, 2, 6]);
+        assert_eq!(
+            cups.after(7, 12).unwrap().collect_vec(),
+            [4, 1, 9, 2, 6, 5, 8, 3, 7, 4, 1, 9]
+        );
+        assert_eq!(cups.position(8), Ok(0));
+        assert_eq!(cups.position(5), Ok(8));
+        assert_eq!(
+            cups.arrangement().collect_vec(),
+            [8, 3, 7, 4, 1, 9, 2, 6, 5]
+        );
+        assert_eq!(
+            cups.position(10),
+            Err(Error::OutOfRange { label: 10, cups: 9 })
+        );
+        assert!(cups.after(0, 1).is_err());
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(parse("389125467\n"), Ok(vec![3, 8, 9, 1, 2, 5, 4, 6, 7]));
+        assert_eq!(
+            parse("10, 3 8,9 1 2 5 4 6 7"),
+            Ok(vec![10, 3, 8, 9, 1, 2, 5, 4, 6, 7])
+        );
+        assert_eq!(parse("1245"), Err(Error::OutOfRange { label: 5, cups: 4 }));
+        assert_eq!(parse("1,0"), Err(Error::OutOfRange { label: 0, cups: 2 }));
+        assert_eq!(parse("1224"), Err(Error::Repeated(2)));
+        assert_eq!(parse("12a"), Err(Error::Label("a".to_owned())));
+        assert_eq!(parse("1, 2, x3"), Err(Error::Label("x3".to_owned())));
+        assert_eq!(parse(" "), Err(Error::Empty));
+    }
+
+    #[test]
+    fn test_rules() {
+        let cups = |input, rules| {
+            let mut cups = Cups::try_from((input, None)).unwrap().with_rules(rules)?;
+            cups.iterate();
+            Ok(cups.to_string())
+        };
+        let rules = |pick_up, destination, advance| Rules {
+            pick_up,
+            destination,
+            advance,
+        };
+        // 3 picks up 8, 9 and 1, and puts them down after 4.
+        assert_eq!(
+            cups("389125467", rules(3, Direction::Higher, 1)),
+            Ok("3 (2) 5 4 8 9 1 6 7".to_owned())
+        );
+        // 3 picks up 8 and 9, and puts them down after 2.
+        assert_eq!(
+            cups("389125467", rules(2, Direction::Lower, 2)),
+            Ok("3 1 (2) 8 9 5 4 6 7".to_owned())
+        );
+        assert_eq!(
+            cups("123", rules(2, Direction::Lower, 1)),
+            Ok("1 (2) 3".to_owned())
+        );
+        assert_eq!(
+            cups("123", rules(3, Direction::Lower, 1)),
+            Err(Error::PickUp {
+                pick_up: 3,
+                cups: 3
+            })
         );
     }
 }
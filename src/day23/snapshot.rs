@@ -0,0 +1,110 @@
+use std::io::{self, Read, Write};
+
+use super::{check, Cups, Error};
+
+// Snapshots are the magic bytes, then the number of cups and the current
+// cup as little-endian u32s, the number of moves played as a little-endian
+// u64, and the labels clockwise from the first cup as little-endian u32s.
+const MAGIC: &[u8; 4] = b"CUPS";
+
+fn read<const N: usize>(reader: &mut impl Read, what: &str) -> Result<[u8; N], Error> {
+    let mut bytes = [0; N];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|_| Error::Snapshot(format!("missing {what}")))?;
+    Ok(bytes)
+}
+
+impl Cups {
+    pub(crate) fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.size() as u32).to_le_bytes())?;
+        writer.write_all(&self.current.to_le_bytes())?;
+        writer.write_all(&(self.moves as u64).to_le_bytes())?;
+        for cup in self.next_cups(self.first).take(self.size()) {
+            writer.write_all(&cup.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub(crate) fn restore(mut reader: impl Read) -> Result<Self, Error> {
+        if &read(&mut reader, "header")? != MAGIC {
+            return Err(Error::Snapshot("bad header".to_owned()));
+        }
+        let size = u32::from_le_bytes(read(&mut reader, "size")?) as usize;
+        let current = u32::from_le_bytes(read(&mut reader, "current cup")?);
+        let moves = u64::from_le_bytes(read(&mut reader, "moves")?) as usize;
+        let cups: Vec<u32> = (0..size)
+            .map(|_| Ok(u32::from_le_bytes(read(&mut reader, "cups")?)))
+            .collect::<Result<_, Error>>()?;
+        check(&cups)?;
+        if !(1..=size as u32).contains(&current) {
+            return Err(Error::OutOfRange {
+                label: current,
+                cups: size,
+            });
+        }
+        let mut cups = Cups::new(cups);
+        cups.current = current;
+        cups.moves = moves;
+        Ok(cups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Rules;
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut cups = Cups::try_from(("389125467", Some(20))).unwrap();
+        for _ in 0..10 {
+            cups.iterate();
+        }
+        let mut snapshot = Vec::new();
+        cups.save(&mut snapshot).unwrap();
+        assert_eq!(snapshot.len(), 4 * 25);
+        let mut restored = Cups::restore(snapshot.as_slice()).unwrap();
+        assert_eq!(restored.to_string(), cups.to_string());
+        assert_eq!(restored.moves, 10);
+        for _ in 0..10 {
+            cups.iterate();
+            restored.iterate();
+        }
+        assert_eq!(restored.to_string(), cups.to_string());
+        assert_eq!(restored.rules, Rules::default());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut snapshot = Vec::new();
+        Cups::try_from(("312", None))
+            .unwrap()
+            .save(&mut snapshot)
+            .unwrap();
+        let restore = |snapshot: &[u8]| Cups::restore(snapshot).err();
+        assert_eq!(
+            restore(&snapshot[..10]),
+            Some(Error::Snapshot("missing current cup".to_owned()))
+        );
+        assert_eq!(
+            restore(&snapshot[..18]),
+            Some(Error::Snapshot("missing moves".to_owned()))
+        );
+        assert_eq!(
+            restore(&snapshot[..26]),
+            Some(Error::Snapshot("missing cups".to_owned()))
+        );
+        assert_eq!(
+            restore(b"SPUC"),
+            Some(Error::Snapshot("bad header".to_owned()))
+        );
+        let mut bad = snapshot.clone();
+        bad[8] = 4;
+        assert_eq!(restore(&bad), Some(Error::OutOfRange { label: 4, cups: 3 }));
+        bad = snapshot;
+        bad[20] = 2;
+        assert_eq!(restore(&bad), Some(Error::Repeated(2)));
+    }
+}
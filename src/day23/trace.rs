@@ -0,0 +1,119 @@
+use std::{
+    io::{self, Write},
+    num::NonZeroUsize,
+};
+
+use itertools::Itertools;
+
+use super::Cups;
+
+// Games with at most this many cups are traced in full, like the puzzle's
+// example. Larger ones get a line every so many moves.
+const FULL_CUPS: usize = 20;
+
+impl Cups {
+    // The cups as the puzzle shows them before move `number`: the current
+    // cup is shifted along one place per move, wrapping around.
+    fn render(&self, number: usize) -> String {
+        let size = self.size();
+        let start = self.next_cups(self.current).nth(size - (number - 1) % size);
+        self.next_cups(start.unwrap())
+            .take(size)
+            .map(|cup| {
+                if cup == self.current {
+                    format!("({cup})")
+                } else {
+                    format!(" {cup} ")
+                }
+            })
+            .join("")
+    }
+}
+
+// Plays on until `moves` moves have been played, writing the current cup,
+// the cups picked up and the destination of each.
+pub(crate) fn trace(
+    cups: &mut Cups,
+    moves: usize,
+    every: NonZeroUsize,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let full = cups.size() <= FULL_CUPS;
+    for number in cups.moves + 1..=moves {
+        if full {
+            writeln!(output, "-- move {number} --")?;
+            writeln!(output, "cups: {}", cups.render(number))?;
+        }
+        let current = cups.current;
+        let destination = cups.iterate();
+        // The picked up cups now follow the destination.
+        let picked = cups
+            .next_cups(destination)
+            .skip(1)
+            .take(cups.rules.pick_up)
+            .join(", ");
+        if full {
+            writeln!(output, "pick up: {picked}")?;
+            writeln!(output, "destination: {destination}\n")?;
+        } else if number % every == 0 {
+            writeln!(
+                output,
+                "move {number}: current {current}, pick up {picked}, destination {destination}"
+            )?;
+        }
+    }
+    if full {
+        writeln!(output, "-- final --")?;
+        writeln!(output, "cups: {}", cups.render(moves + 1))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let mut cups = Cups::try_from(("389125467", None)).unwrap();
+        let mut output = Vec::new();
+        trace(&mut cups, 10, NonZeroUsize::MIN, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(
+            "-- move 1 --
+cups: (3) 8  9  1  2  5  4  6  7 \n\
+pick up: 8, 9, 1
+destination: 2
+
+-- move 2 --
+cups:  3 (2) 8  9  1  5  4  6  7 \n\
+pick up: 8, 9, 1
+destination: 7
+
+-- move 3 --
+cups:  3  2 (5) 4  6  7  8  9  1 \n\
+pick up: 4, 6, 7
+destination: 3
+"
+        ));
+        assert!(output.contains(
+            "-- move 10 --
+cups: (5) 7  4  1  8  3  9  2  6 \n"
+        ));
+        assert!(output.ends_with(
+            "-- final --
+cups:  5 (8) 3  7  4  1  9  2  6 \n"
+        ));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut cups = Cups::try_from(("389125467", Some(100))).unwrap();
+        let mut output = Vec::new();
+        trace(&mut cups, 100, NonZeroUsize::new(25).unwrap(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 4);
+        assert!(output.starts_with("move 25: current "));
+        assert!(output.lines().last().unwrap().starts_with("move 100: "));
+    }
+}
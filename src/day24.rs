@@ -1,72 +1,241 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
-use derive_more::Add;
-use derive_more::Sub;
-use derive_more::Sum;
 use itertools::Itertools;
 
-#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Add, Sub, Sum)]
-struct Hex(isize, isize, isize);
-
-impl Hex {
-    /*      i
-     *      |
-     *    /  \
-     *   |    |
-     *  / \  / \
-     * k         j
-     */
-    const I: Hex = Hex(1, 0, 0);
-    const J: Hex = Hex(0, 1, 0);
-    const K: Hex = Hex(0, 0, 1);
-
-    fn neighbours(self) -> impl IntoIterator<Item = Hex> {
-        [Self::I, Self::J, Self::K]
-            .into_iter()
-            .tuple_combinations()
-            .flat_map(|(a, b)| [a - b, b - a])
-            .map(move |vector| self + vector)
+use self::format::Format;
+use crate::hex::Hex;
+
+mod ascii;
+mod dense;
+mod format;
+mod patterns;
+mod stats;
+mod svg;
+
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+pub(crate) struct Options {
+    /// The rule tiles flip by, in B/S notation: the numbers of black
+    /// neighbours that turn a white tile black, and that keep a black tile
+    /// black
+    #[arg(long, default_value_t = Rule::default())]
+    rule: Rule,
+    /// Read the tiles from this file instead of the puzzle input
+    #[arg(long, value_name = "FILE")]
+    input: Option<PathBuf>,
+    /// How tiles are written in the input: pointy-topped directions as in
+    /// the puzzle, flat-topped directions (n, ne, se, s, sw, nw), axial
+    /// coordinates "q,r" or cube coordinates "x,y,z"
+    #[arg(long, value_enum, default_value_t = Format::Pointy)]
+    format: Format,
+    /// Print the black tiles after the last day in this format, as
+    /// shortest paths for directions
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    encode: Option<Format>,
+    /// Classify the patterns in these files, in --format, as still lifes,
+    /// oscillators or translating patterns
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    patterns: Vec<PathBuf>,
+    /// The number of generations to evolve patterns for when classifying
+    /// them
+    #[arg(long, default_value_t = 1000)]
+    generations: usize,
+    /// How the tiles are stored while they're flipped
+    #[arg(long, value_enum, default_value_t = Backend::Sparse)]
+    backend: Backend,
+    /// The number of threads for the threaded backend [default: one per
+    /// CPU]
+    #[arg(long)]
+    threads: Option<usize>,
+    /// The number of days to flip tiles for
+    #[arg(long, default_value_t = 100)]
+    days: usize,
+    /// Draw the tiles after the last day as an SVG image
+    #[arg(long, value_name = "FILE")]
+    svg: Option<PathBuf>,
+    /// Draw the tiles on each day as SVG images in this directory
+    #[arg(long, value_name = "DIR")]
+    frames: Option<PathBuf>,
+    /// Write the number of black tiles, births, deaths, extent along each
+    /// cube axis and centroid on each day to this file as CSV
+    #[arg(long, value_name = "FILE")]
+    stats: Option<PathBuf>,
+    /// Print the tiles after the last day, `#` for black and `.` for white
+    #[arg(long)]
+    ascii: bool,
+    /// Print the tiles on each day in turn
+    #[arg(long)]
+    animate: bool,
+    /// The time between days when animating
+    #[arg(long, default_value_t = 100, value_name = "MS")]
+    delay: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            rule: Rule::default(),
+            input: None,
+            format: Format::Pointy,
+            encode: None,
+            patterns: Vec::new(),
+            generations: 1000,
+            backend: Backend::Sparse,
+            threads: None,
+            days: 100,
+            svg: None,
+            frames: None,
+            stats: None,
+            ascii: false,
+            animate: false,
+            delay: 100,
+        }
     }
 }
 
-impl TryFrom<&str> for Hex {
-    type Error = String;
+#[derive(Debug, PartialEq, Eq, derive_more::Display)]
+pub(crate) enum Error {
+    #[display(fmt = "Invalid rule {:?}: {}", _0, _1)]
+    Rule(String, &'static str),
+    #[display(fmt = "{}", _0)]
+    Io(String),
+    #[display(fmt = "{}", "_0.iter().join(\"\\n\")")]
+    Tiles(Vec<format::Invalid>),
+}
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "e" => Ok(Self::J - Self::K),
-            "w" => Ok(Self::K - Self::J),
-            "se" => Ok(Self::J - Self::I),
-            "nw" => Ok(Self::I - Self::J),
-            "sw" => Ok(Self::K - Self::I),
-            "ne" => Ok(Self::I - Self::K),
-            _ => Err(format!("Invalid direction: {value}")),
+// So that clap can report rules that don't parse.
+impl std::error::Error for Error {}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Backend {
+    // A set of the black tiles.
+    Sparse,
+    // A grid of every tile around the black ones.
+    Dense,
+    // The dense grid, split into bands computed on worker threads.
+    Threaded,
+}
+
+// A way of storing the black tiles, which can flip them to the next day.
+trait Floor {
+    fn new(tiles: HashSet<Hex>, options: &Options) -> Self;
+    fn mutate(self, rule: &Rule) -> Self;
+    fn tiles(&self) -> HashSet<Hex>;
+}
+
+impl Floor for HashSet<Hex> {
+    fn new(tiles: HashSet<Hex>, _: &Options) -> Self {
+        tiles
+    }
+
+    fn mutate(self, rule: &Rule) -> Self {
+        mutate(self, rule)
+    }
+
+    fn tiles(&self) -> HashSet<Hex> {
+        self.clone()
+    }
+}
+
+// Bit n of `birth` is set if a white tile with n black neighbours turns
+// black, and likewise for `survival` and black tiles staying black.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rule {
+    birth: u8,
+    survival: u8,
+}
+
+impl Rule {
+    fn next(&self, black: bool, neighbours: usize) -> bool {
+        let counts = if black { self.survival } else { self.birth };
+        counts & 1 << neighbours != 0
+    }
+}
+
+// The puzzle's rule.
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            birth: 1 << 2,
+            survival: 1 << 1 | 1 << 2,
         }
     }
 }
 
-fn iter_vectors(s: &str) -> Vec<Hex> {
-    let mut result = Vec::new();
-    let mut i = 0;
-    let mut j = 1;
-    while j <= s.len() && j - i < 3 {
-        if let Ok(vector) = s[i..j].try_into() {
-            result.push(vector);
-            i = j;
+impl FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| Error::Rule(s.to_owned(), reason);
+        let counts = |part: &str, prefix| {
+            let digits = part
+                .strip_prefix(prefix)
+                .ok_or_else(|| error("expected B<counts>/S<counts>"))?;
+            digits
+                .chars()
+                .try_fold(0u8, |counts, c| match c.to_digit(10) {
+                    Some(n @ 0..=6) => Ok(counts | 1 << n),
+                    _ => Err(error("counts must be 0 to 6")),
+                })
+        };
+        let (birth, survival) = s
+            .split_once('/')
+            .ok_or_else(|| error("expected B<counts>/S<counts>"))?;
+        let rule = Self {
+            birth: counts(birth, 'B')?,
+            survival: counts(survival, 'S')?,
+        };
+        if rule.birth & 1 != 0 {
+            return Err(error("B0 would turn infinitely many tiles black"));
         }
-        j += 1;
+        Ok(rule)
     }
-    assert_eq!(i, s.len(), "Invalid directions: {s}");
-    result
 }
 
-fn generate(input: &str) -> HashSet<Hex> {
-    input
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |counts: u8| (0..=6).filter(|n| counts & 1 << n != 0).join("");
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))
+    }
+}
+
+impl TryFrom<&str> for Hex {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        format::POINTY
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|&(_, vector)| vector)
+            .ok_or_else(|| format!("Invalid direction: {value}"))
+    }
+}
+
+// Reads the tiles to flip, reporting every line that isn't a tile.
+fn generate(input: &str, format: Format) -> Result<HashSet<Hex>, Error> {
+    let (tiles, invalid): (Vec<_>, Vec<_>) = input
         .lines()
-        .map(str::trim)
-        .map(iter_vectors)
-        .map(|v| v.into_iter().sum())
+        .enumerate()
+        // An empty path leads to the reference tile, but an empty line of
+        // coordinates is just an empty line.
+        .filter(|(_, line)| {
+            !line.trim().is_empty() || matches!(format, Format::Pointy | Format::Flat)
+        })
+        .map(|(number, line)| {
+            format::parse(line, format).map_err(|invalid| invalid.on_line(number + 1))
+        })
+        .partition_result();
+    if !invalid.is_empty() {
+        return Err(Error::Tiles(invalid));
+    }
+    Ok(tiles
+        .into_iter()
         .fold(HashSet::<Hex>::new(), |mut hs, hex| {
             if hs.contains(&hex) {
                 hs.remove(&hex);
@@ -74,10 +243,10 @@ fn generate(input: &str) -> HashSet<Hex> {
                 hs.insert(hex);
             }
             hs
-        })
+        }))
 }
 
-fn mutate(mut black_tiles: HashSet<Hex>) -> HashSet<Hex> {
+fn mutate(mut black_tiles: HashSet<Hex>, rule: &Rule) -> HashSet<Hex> {
     let mut black_counts: HashMap<Hex, usize> = HashMap::new();
     for tile in black_tiles.iter() {
         black_counts.entry(*tile).or_default();
@@ -86,11 +255,12 @@ fn mutate(mut black_tiles: HashSet<Hex>) -> HashSet<Hex> {
         }
     }
     for (tile, count) in black_counts {
-        match (black_tiles.contains(&tile), count) {
-            (true, 0 | 3..) => {
+        let black = black_tiles.contains(&tile);
+        match (black, rule.next(black, count)) {
+            (true, false) => {
                 black_tiles.remove(&tile);
             }
-            (false, 2) => {
+            (false, true) => {
                 black_tiles.insert(tile);
             }
             _ => (),
@@ -99,14 +269,96 @@ fn mutate(mut black_tiles: HashSet<Hex>) -> HashSet<Hex> {
     black_tiles
 }
 
-pub(crate) fn solve(input: &str) -> usize {
-    generate(input).len()
+fn write(path: &PathBuf, contents: String) -> Result<(), Error> {
+    fs::write(path, contents).map_err(|err| Error::Io(format!("{}: {err}", path.display())))
+}
+
+// Flips the tiles for `days` days, returning the tiles on each day if
+// `keep` is set, or just the last day otherwise.
+fn evolve<F: Floor>(
+    tiles: HashSet<Hex>,
+    days: usize,
+    options: &Options,
+    keep: bool,
+) -> Vec<HashSet<Hex>> {
+    let rule = &options.rule;
+    let mut floor = F::new(tiles, options);
+    let mut history = Vec::new();
+    for _ in 0..days {
+        if keep {
+            history.push(floor.tiles());
+        }
+        floor = floor.mutate(rule);
+    }
+    history.push(floor.tiles());
+    history
 }
 
-pub(crate) fn solve_2(input: &str) -> usize {
-    (0..100)
-        .fold(generate(input), |tiles, _| mutate(tiles))
-        .len()
+// Flips the tiles for `days` days, drawing them as the options say.
+fn run(tiles: HashSet<Hex>, days: usize, options: &Options) -> Result<usize, Error> {
+    let keep = options.frames.is_some() || options.stats.is_some() || options.animate;
+    let history = match options.backend {
+        Backend::Sparse => evolve::<HashSet<Hex>>(tiles, days, options, keep),
+        Backend::Dense => evolve::<dense::Grid>(tiles, days, options, keep),
+        Backend::Threaded => evolve::<dense::Threaded>(tiles, days, options, keep),
+    };
+    let tiles = history.last().unwrap();
+    if let Some(dir) = &options.frames {
+        fs::create_dir_all(dir).map_err(|err| Error::Io(format!("{}: {err}", dir.display())))?;
+        let bounds = svg::Bounds::new(history.iter().flatten());
+        for (day, tiles) in history.iter().enumerate() {
+            write(
+                &dir.join(format!("day-{day:03}.svg")),
+                svg::render(tiles, bounds),
+            )?;
+        }
+    }
+    if let Some(path) = &options.stats {
+        write(path, stats::csv(&stats::series(&history)))?;
+    }
+    if options.animate {
+        let delay = Duration::from_millis(options.delay);
+        ascii::animate(&history, delay, std::io::stdout().lock())
+            .map_err(|err| Error::Io(err.to_string()))?;
+    }
+    if options.ascii {
+        print!("{}", ascii::render(tiles, ascii::Bounds::new(tiles)));
+    }
+    let reports = patterns::library(
+        &options.patterns,
+        options.format,
+        &options.rule,
+        options.generations,
+    )?;
+    for report in reports {
+        println!("{report}");
+    }
+    if let Some(format) = options.encode {
+        for tile in tiles.iter().sorted() {
+            println!("{}", format::encode(*tile, format));
+        }
+    }
+    if let Some(path) = &options.svg {
+        write(path, svg::render(tiles, svg::Bounds::new(tiles)))?;
+    }
+    Ok(tiles.len())
+}
+
+fn read(input: &str, options: &Options) -> Result<HashSet<Hex>, Error> {
+    let input = match &options.input {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| Error::Io(format!("{}: {err}", path.display())))?,
+        None => input.to_owned(),
+    };
+    generate(&input, options.format)
+}
+
+pub(crate) fn solve_with(input: &str, options: &Options) -> Result<usize, Error> {
+    run(read(input, options)?, 0, options)
+}
+
+pub(crate) fn solve_2_with(input: &str, options: &Options) -> Result<usize, Error> {
+    run(read(input, options)?, options.days, options)
 }
 
 #[cfg(test)]
@@ -114,7 +366,7 @@ mod tests {
 
     use super::*;
 
-    const EXAMPLE: &str = "
+    pub(super) const EXAMPLE: &str = "
         sesenwnenenewseeswwswswwnenewsewsw
         neeenesenwnwwswnenewnwwsewnenwseswesw
         seswneswswsenwwnwse
@@ -140,7 +392,7 @@ mod tests {
     #[test]
     fn test_directions() {
         fn apply_dirs(dirs: &str) -> Hex {
-            iter_vectors(dirs).into_iter().sum()
+            format::parse(dirs, Format::Pointy).unwrap()
         }
         assert_eq!(apply_dirs("nwwswee"), Hex::default());
         assert_eq!(apply_dirs("esew"), apply_dirs("se"));
@@ -148,13 +400,23 @@ mod tests {
 
     #[test]
     fn test_small() {
-        assert_eq!(solve("esew"), 1);
-        assert_eq!(solve("nwwswee"), 1);
+        assert_eq!(solve_with("esew", &Options::default()), Ok(1));
+        assert_eq!(solve_with("nwwswee", &Options::default()), Ok(1));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let err = solve_with("esew\nexw\n\nnwwswee\n  seq", &Options::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, character 2: \"x\": not a direction\n\
+             line 5, character 5: \"q\": not a direction"
+        );
     }
 
     #[test]
     fn test_solve() {
-        assert_eq!(solve(EXAMPLE), 10);
+        assert_eq!(solve_with(EXAMPLE, &Options::default()), Ok(10));
     }
 
     #[test]
@@ -172,11 +434,61 @@ mod tests {
             wne
             wnw
         ";
-        assert_eq!(neighbours, generate(all_dirs));
+        assert_eq!(neighbours, generate(all_dirs, Format::Pointy).unwrap());
     }
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2(EXAMPLE), 2208);
+        assert_eq!(solve_2_with(EXAMPLE, &Options::default()), Ok(2208));
+    }
+
+    #[test]
+    fn test_encode() {
+        let tiles = generate(EXAMPLE, Format::Pointy).unwrap();
+        // Make sure the reference tile's empty path is covered.
+        assert!(tiles.contains(&Hex::default()));
+        for format in [Format::Pointy, Format::Flat, Format::Axial, Format::Cube] {
+            // End every line, so that the reference tile's blank line isn't
+            // dropped when it comes last.
+            let encoded: String = tiles
+                .iter()
+                .map(|&tile| format::encode(tile, format) + "\n")
+                .collect();
+            assert_eq!(generate(&encoded, format), Ok(tiles.clone()));
+        }
+    }
+
+    #[test]
+    fn test_rule() {
+        assert_eq!("B2/S12".parse(), Ok(Rule::default()));
+        assert_eq!(Rule::default().to_string(), "B2/S12");
+        assert_eq!("B/S".parse::<Rule>().unwrap().to_string(), "B/S");
+        assert_eq!(
+            "B35/S0246".parse::<Rule>().unwrap().to_string(),
+            "B35/S0246"
+        );
+        for (rule, reason) in [
+            ("B2", "expected B<counts>/S<counts>"),
+            ("S12/B2", "expected B<counts>/S<counts>"),
+            ("B2/S17", "counts must be 0 to 6"),
+            ("B02/S1", "B0 would turn infinitely many tiles black"),
+        ] {
+            assert_eq!(
+                rule.parse::<Rule>(),
+                Err(Error::Rule(rule.to_owned(), reason))
+            );
+        }
+        // With no births, the black tiles can only die out: some of them
+        // here, and all at once with no survivals either.
+        let options = |rule: &str, days| Options {
+            rule: rule.parse().unwrap(),
+            days,
+            ..Default::default()
+        };
+        assert_eq!(solve_2_with(EXAMPLE, &options("B/S12", 10)), Ok(7));
+        assert_eq!(solve_2_with(EXAMPLE, &options("B/S", 1)), Ok(0));
+        // Without deaths, they're all still there.
+        assert_eq!(solve_2_with(EXAMPLE, &options("B/S0123456", 10)), Ok(10));
+        assert_eq!(solve_2_with(EXAMPLE, &options("B2/S12", 10)), Ok(37));
     }
 }
@@ -0,0 +1,109 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use super::Hex;
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+// Tiles are drawn in rows, ne going up a row and half a tile right, so a
+// tile's column in half-tiles is 2j + i for `Hex(i, j, k)`.
+fn position(Hex(i, j, _): Hex) -> (isize, isize) {
+    (-i, 2 * j + i)
+}
+
+// The rows and half-tile columns that every frame of an animation shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Bounds {
+    rows: (isize, isize),
+    columns: (isize, isize),
+}
+
+impl Bounds {
+    pub(crate) fn new<'a>(tiles: impl IntoIterator<Item = &'a Hex>) -> Self {
+        let mut positions = tiles.into_iter().map(|&tile| position(tile));
+        // An empty floor is drawn as the reference tile alone.
+        let (row, column) = positions.next().unwrap_or_default();
+        let mut bounds = Self {
+            rows: (row, row),
+            columns: (column, column),
+        };
+        for (row, column) in positions {
+            bounds.rows = (bounds.rows.0.min(row), bounds.rows.1.max(row));
+            bounds.columns = (bounds.columns.0.min(column), bounds.columns.1.max(column));
+        }
+        bounds
+    }
+}
+
+// Draws black tiles as `#` and white ones as `.`, offsetting alternate
+// rows by half a tile.
+pub(crate) fn render(tiles: &HashSet<Hex>, bounds: Bounds) -> String {
+    let mut result = String::new();
+    for row in bounds.rows.0..=bounds.rows.1 {
+        let mut line = String::new();
+        for column in bounds.columns.0..=bounds.columns.1 {
+            // Tiles only sit in columns of the same parity as their row.
+            if (column + row).rem_euclid(2) != 0 {
+                line.push(' ');
+                continue;
+            }
+            let i = -row;
+            let tile = Hex(i, (column - i) / 2, -i - (column - i) / 2);
+            line.push(if tiles.contains(&tile) { '#' } else { '.' });
+        }
+        result += line.trim_end();
+        result.push('\n');
+    }
+    result
+}
+
+// Draws each day in turn, `delay` apart.
+pub(crate) fn animate(
+    days: &[HashSet<Hex>],
+    delay: Duration,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let bounds = Bounds::new(days.iter().flatten());
+    for (day, tiles) in days.iter().enumerate() {
+        write!(output, "{CLEAR}Day {day}: {}\n\n", tiles.len())?;
+        write!(output, "{}", render(tiles, bounds))?;
+        output.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{format::Format, generate};
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let tiles = generate("e\nne\nnw\nnwnw\nw", Format::Pointy).unwrap();
+        assert_eq!(render(&tiles, Bounds::new(&tiles)), "# . .\n # #\n# . #\n");
+        // Only the tiles are framed, however far they are from the
+        // reference tile.
+        let tiles = generate("100,100\n101,100", Format::Axial).unwrap();
+        assert_eq!(render(&tiles, Bounds::new(&tiles)), "# #\n");
+        assert_eq!(render(&HashSet::new(), Bounds::new([])), ".\n");
+    }
+
+    #[test]
+    fn test_animate() {
+        let days = [
+            generate("e", Format::Pointy).unwrap(),
+            generate("e\nw", Format::Pointy).unwrap(),
+        ];
+        let mut output = Vec::new();
+        animate(&days, Duration::ZERO, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{CLEAR}Day 0: 1\n\n. . #\n{CLEAR}Day 1: 2\n\n# . #\n")
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashSet, thread};
+
+use crate::hex::DIRECTIONS;
+
+use super::{Floor, Hex, Options, Rule};
+
+// The tiles in a rectangle of axial coordinates, row by row. It grows to
+// keep a border of white tiles around the black ones, which is as far as
+// they can spread in a day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Grid {
+    min: (isize, isize),
+    size: (usize, usize),
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    fn empty(min: (isize, isize), max: (isize, isize)) -> Self {
+        let size = ((max.0 - min.0 + 1) as usize, (max.1 - min.1 + 1) as usize);
+        Self {
+            min,
+            size,
+            cells: vec![false; size.0 * size.1],
+        }
+    }
+
+    fn index(&self, (i, j): (isize, isize)) -> Option<usize> {
+        let (row, column) = (i - self.min.0, j - self.min.1);
+        if (0..self.size.0 as isize).contains(&row) && (0..self.size.1 as isize).contains(&column) {
+            Some(row as usize * self.size.1 + column as usize)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, position: (isize, isize)) -> bool {
+        self.index(position).is_some_and(|index| self.cells[index])
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        (0..self.size.0 as isize).flat_map(move |row| {
+            (0..self.size.1 as isize).map(move |column| (self.min.0 + row, self.min.1 + column))
+        })
+    }
+
+    fn black(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        self.positions().filter(|&position| self.get(position))
+    }
+
+    // A grid covering `positions` and the tiles around them.
+    fn around(positions: impl Iterator<Item = (isize, isize)>) -> Self {
+        let (min, max) = positions.fold(
+            ((isize::MAX, isize::MAX), (isize::MIN, isize::MIN)),
+            |(min, max), (i, j)| ((min.0.min(i), min.1.min(j)), (max.0.max(i), max.1.max(j))),
+        );
+        if min.0 > max.0 {
+            return Self::empty((0, 0), (0, 0));
+        }
+        Self::empty((min.0 - 1, min.1 - 1), (max.0 + 1, max.1 + 1))
+    }
+
+    // The next day's grid. Its rows are split into `bands` bands, which are
+    // computed on their own threads if there's more than one.
+    fn step(&self, rule: &Rule, bands: usize) -> Self {
+        let mut next = Self::around(self.black());
+        let (min, width) = (next.min, next.size.1);
+        let band_rows = next.size.0.div_ceil(bands);
+        let fill = |band: usize, cells: &mut [bool]| {
+            for (index, cell) in cells.iter_mut().enumerate() {
+                let index = band * band_rows * width + index;
+                let position = (
+                    min.0 + (index / width) as isize,
+                    min.1 + (index % width) as isize,
+                );
+                *cell = rule.next(self.get(position), self.neighbours(position));
+            }
+        };
+        if bands == 1 {
+            fill(0, &mut next.cells);
+        } else {
+            thread::scope(|scope| {
+                for (band, cells) in next.cells.chunks_mut(band_rows * width).enumerate() {
+                    scope.spawn(move || fill(band, cells));
+                }
+            });
+        }
+        next
+    }
+
+    fn neighbours(&self, (i, j): (isize, isize)) -> usize {
+        DIRECTIONS
+            .iter()
+            .filter(|&&Hex(di, dj, _)| self.get((i + di, j + dj)))
+            .count()
+    }
+}
+
+impl Floor for Grid {
+    fn new(tiles: HashSet<Hex>, _: &Options) -> Self {
+        let positions = || tiles.iter().map(|&Hex(i, j, _)| (i, j));
+        let mut grid = Self::around(positions());
+        for position in positions() {
+            let index = grid.index(position).unwrap();
+            grid.cells[index] = true;
+        }
+        grid
+    }
+
+    fn mutate(self, rule: &Rule) -> Self {
+        self.step(rule, 1)
+    }
+
+    fn tiles(&self) -> HashSet<Hex> {
+        self.black().map(|(i, j)| Hex(i, j, -i - j)).collect()
+    }
+}
+
+// A grid whose days are computed in bands of rows on worker threads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Threaded {
+    grid: Grid,
+    threads: usize,
+}
+
+impl Floor for Threaded {
+    fn new(tiles: HashSet<Hex>, options: &Options) -> Self {
+        let threads = options
+            .threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from));
+        Self {
+            grid: Grid::new(tiles, options),
+            threads: threads.max(1),
+        }
+    }
+
+    fn mutate(self, rule: &Rule) -> Self {
+        Self {
+            grid: self.grid.step(rule, self.threads),
+            threads: self.threads,
+        }
+    }
+
+    fn tiles(&self) -> HashSet<Hex> {
+        self.grid.tiles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Rng;
+
+    use super::super::{format::Format, generate, mutate, tests::EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn test_mutate() {
+        let mut sparse = generate(EXAMPLE, Format::Pointy).unwrap();
+        let mut dense = Grid::new(sparse.clone(), &Options::default());
+        assert_eq!(dense.tiles(), sparse);
+        for _ in 0..20 {
+            sparse = mutate(sparse, &Rule::default());
+            dense = dense.mutate(&Rule::default());
+            assert_eq!(dense.tiles(), sparse);
+        }
+        // The black tiles die out, leaving an empty grid.
+        let rule = "B/S".parse().unwrap();
+        let empty = Grid::new(
+            generate(EXAMPLE, Format::Pointy).unwrap(),
+            &Options::default(),
+        )
+        .mutate(&rule)
+        .mutate(&rule);
+        assert!(empty.tiles().is_empty());
+        assert_eq!(empty.size, (1, 1));
+    }
+
+    #[test]
+    fn test_threaded() {
+        // A random pattern in a hexagon of radius 30.
+        let mut rng = Rng::new(24);
+        let tiles: HashSet<_> = (-30..=30)
+            .flat_map(|i| (-30..=30).map(move |j| Hex(i, j, -i - j)))
+            .filter(|&Hex(_, _, k)| (-30..=30).contains(&k))
+            .filter(|_| rng.below(3) == 0)
+            .collect();
+        let options = |threads| Options {
+            threads: Some(threads),
+            ..Default::default()
+        };
+        let mut serial = Grid::new(tiles.clone(), &options(1));
+        let mut threaded = [1, 3, 8].map(|threads| Threaded::new(tiles.clone(), &options(threads)));
+        for _ in 0..100 {
+            serial = serial.mutate(&Rule::default());
+            threaded = threaded.map(|floor| floor.mutate(&Rule::default()));
+            for floor in &threaded {
+                assert_eq!(floor.grid, serial);
+            }
+        }
+        assert!(serial.tiles().len() > 1000);
+    }
+}
@@ -0,0 +1,294 @@
+use itertools::Itertools;
+
+use crate::hex::DIRECTIONS;
+
+use super::Hex;
+
+// How tiles are written in the input.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    // Steps e, se, sw, w, nw and ne between rows of pointy-topped tiles, as
+    // in the puzzle.
+    Pointy,
+    // Steps n, ne, se, s, sw and nw between columns of flat-topped tiles.
+    Flat,
+    // Axial coordinates "q,r", with q counting steps e and r steps se.
+    Axial,
+    // Cube coordinates "x,y,z", with x + y + z = 0.
+    Cube,
+}
+
+// Names `DIRECTIONS` clockwise, starting from the one at `first`.
+const fn named(names: [&'static str; 6], first: usize) -> [(&'static str, Hex); 6] {
+    let mut result = [("", Hex(0, 0, 0)); 6];
+    let mut n = 0;
+    while n < 6 {
+        result[n] = (names[n], DIRECTIONS[(first + n) % 6]);
+        n += 1;
+    }
+    result
+}
+
+pub(crate) const POINTY: [(&str, Hex); 6] = named(["e", "se", "sw", "w", "nw", "ne"], 0);
+
+// The flat-topped directions, clockwise from n, which is pointy-topped nw.
+pub(crate) const FLAT: [(&str, Hex); 6] = named(["n", "ne", "se", "s", "sw", "nw"], 4);
+
+impl Format {
+    fn directions(self) -> Option<&'static [(&'static str, Hex); 6]> {
+        match self {
+            Format::Pointy => Some(&POINTY),
+            Format::Flat => Some(&FLAT),
+            Format::Axial | Format::Cube => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, derive_more::Display)]
+pub(crate) enum Problem {
+    #[display(fmt = "not a direction")]
+    Direction,
+    #[display(fmt = "not a number")]
+    Number,
+    #[display(fmt = "expected {} coordinates", _0)]
+    Count(usize),
+    #[display(fmt = "coordinates don't sum to zero")]
+    Sum,
+}
+
+// A piece of input that isn't a tile, found `offset` characters into its
+// line. Lines and characters count from 1, as editors do.
+#[derive(Debug, PartialEq, Eq, derive_more::Display)]
+#[display(
+    fmt = "line {}, character {}: {:?}: {}",
+    line,
+    offset,
+    fragment,
+    problem
+)]
+pub(crate) struct Invalid {
+    line: usize,
+    offset: usize,
+    fragment: String,
+    problem: Problem,
+}
+
+impl Invalid {
+    // `fragment` is the part of `line` starting `start` bytes in.
+    fn new(line: &str, start: usize, fragment: &str, problem: Problem) -> Self {
+        Self {
+            line: 1,
+            offset: line[..start].chars().count() + 1,
+            fragment: fragment.to_owned(),
+            problem,
+        }
+    }
+
+    pub(crate) fn on_line(self, line: usize) -> Self {
+        Self { line, ..self }
+    }
+}
+
+// The byte range of `line` without the whitespace around it.
+fn trimmed(line: &str) -> (usize, usize) {
+    let start = line.len() - line.trim_start().len();
+    (start, start + line.trim().len())
+}
+
+// Splits `line` into directions, preferring two letters to one so that
+// e.g. flat-topped "ne" isn't read as "n" then "e".
+fn steps(line: &str, directions: &[(&str, Hex)]) -> Result<Vec<Hex>, Invalid> {
+    let step_at = |rest: &str| {
+        [2, 1]
+            .into_iter()
+            .filter(|&len| len <= rest.len() && rest.is_char_boundary(len))
+            .find_map(|len| {
+                let step = directions.iter().find(|(name, _)| *name == &rest[..len]);
+                step.map(|&(_, step)| (len, step))
+            })
+    };
+    let (mut start, end) = trimmed(line);
+    let mut result = Vec::new();
+    while start < end {
+        let Some((len, step)) = step_at(&line[start..end]) else {
+            // Report everything up to the next direction, so that a
+            // misspelt word shows up whole.
+            let bad = line[start..end]
+                .char_indices()
+                .map(|(i, _)| start + i)
+                .find(|&i| i > start && step_at(&line[i..end]).is_some())
+                .unwrap_or(end);
+            return Err(Invalid::new(
+                line,
+                start,
+                &line[start..bad],
+                Problem::Direction,
+            ));
+        };
+        result.push(step);
+        start += len;
+    }
+    Ok(result)
+}
+
+fn numbers<const N: usize>(line: &str) -> Result<[isize; N], Invalid> {
+    let mut numbers = Vec::new();
+    let mut start = 0;
+    for piece in line.split(',') {
+        let (from, to) = trimmed(piece);
+        let number = &piece[from..to];
+        let value = number
+            .parse()
+            .map_err(|_| Invalid::new(line, start + from, number, Problem::Number))?;
+        numbers.push(value);
+        start += piece.len() + 1;
+    }
+    let (from, to) = trimmed(line);
+    numbers
+        .try_into()
+        .map_err(|_| Invalid::new(line, from, &line[from..to], Problem::Count(N)))
+}
+
+// Reads a line of input as the tile it leads to. Errors are on line 1,
+// for the caller to renumber.
+pub(crate) fn parse(line: &str, format: Format) -> Result<Hex, Invalid> {
+    match format {
+        Format::Pointy | Format::Flat => {
+            Ok(steps(line, format.directions().unwrap())?.into_iter().sum())
+        }
+        Format::Axial => {
+            let [q, r] = numbers(line)?;
+            Ok(Hex::from_axial(q, r))
+        }
+        Format::Cube => {
+            let [x, y, z] = numbers(line)?;
+            Hex::from_cube(x, y, z).ok_or_else(|| {
+                let (from, to) = trimmed(line);
+                Invalid::new(line, from, &line[from..to], Problem::Sum)
+            })
+        }
+    }
+}
+
+// Writes `hex` in `format`: as one of the shortest paths to it if the
+// format is directions, in which case the reference tile is a blank line.
+pub(crate) fn encode(mut hex: Hex, format: Format) -> String {
+    let origin = Hex::default();
+    match format.directions() {
+        None if format == Format::Axial => {
+            let (q, r) = hex.axial();
+            format!("{q},{r}")
+        }
+        None => hex.cube().iter().join(","),
+        Some(directions) => {
+            let mut result = String::new();
+            while hex != origin {
+                let (name, step) = directions
+                    .iter()
+                    .find(|&&(_, step)| (hex - step).distance(origin) < hex.distance(origin))
+                    .unwrap();
+                result += name;
+                hex = hex - *step;
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_directions() {
+        for directions in [POINTY, FLAT] {
+            let steps: Vec<_> = directions.iter().map(|&(_, step)| step).sorted().collect();
+            let neighbours: Vec<_> = Hex::default().neighbours().into_iter().sorted().collect();
+            assert_eq!(steps, neighbours);
+        }
+        assert_eq!(parse("nwwswee", Format::Pointy), Ok(Hex::default()));
+        assert_eq!(parse("nenwssw", Format::Flat), parse("sw", Format::Pointy));
+        assert_eq!(parse("nnne", Format::Flat), parse("nwnwne", Format::Pointy));
+    }
+
+    #[test]
+    fn test_coordinates() {
+        assert_eq!(parse("1,0", Format::Axial), parse("e", Format::Pointy));
+        assert_eq!(parse("0,1", Format::Axial), parse("se", Format::Pointy));
+        assert_eq!(parse("1,-1", Format::Axial), parse("ne", Format::Pointy));
+        assert_eq!(parse("1, -1, 0", Format::Cube), parse("1,0", Format::Axial));
+        assert_eq!(parse("-2,1,1", Format::Cube), parse("-2,1", Format::Axial));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for hex in Hex::default().spiral(6) {
+            for format in [Format::Pointy, Format::Flat, Format::Axial, Format::Cube] {
+                let encoded = encode(hex, format);
+                assert_eq!(parse(&encoded, format), Ok(hex), "{encoded}");
+            }
+            // Each direction is one step, and two letters at most.
+            let steps = steps(&encode(hex, Format::Pointy), &POINTY).unwrap().len();
+            assert_eq!(steps as isize, hex.distance(Hex::default()));
+        }
+        assert_eq!(
+            encode(parse("esew", Format::Pointy).unwrap(), Format::Pointy),
+            "se"
+        );
+        assert_eq!(encode(Hex::default(), Format::Flat), "");
+    }
+
+    #[test]
+    fn test_distance() {
+        // No path is shorter than the distance, and encoding takes one of
+        // that length.
+        let mut rng = Rng::new(24);
+        for format in [Format::Pointy, Format::Flat] {
+            let directions = format.directions().unwrap();
+            for _ in 0..500 {
+                let length = rng.below(21);
+                let line: String = (0..length).map(|_| directions[rng.below(6)].0).collect();
+                let hex = parse(&line, format).unwrap();
+                let distance = hex.distance(Hex::default());
+                assert!(distance <= length as isize, "{line}");
+                let path = steps(&encode(hex, format), directions).unwrap();
+                assert_eq!(path.len() as isize, distance, "{line}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let invalid = |line, format| parse(line, format).unwrap_err().to_string();
+        assert_eq!(
+            invalid("  eswxyzne", Format::Pointy),
+            "line 1, character 6: \"xyz\": not a direction"
+        );
+        assert_eq!(
+            invalid("eé", Format::Pointy),
+            "line 1, character 2: \"é\": not a direction"
+        );
+        assert_eq!(
+            invalid("nee", Format::Flat),
+            "line 1, character 3: \"e\": not a direction"
+        );
+        assert_eq!(
+            invalid("1, x", Format::Axial),
+            "line 1, character 4: \"x\": not a number"
+        );
+        assert_eq!(
+            invalid("1,2,", Format::Cube),
+            "line 1, character 5: \"\": not a number"
+        );
+        assert_eq!(
+            invalid(" 1,2,3", Format::Axial),
+            "line 1, character 2: \"1,2,3\": expected 2 coordinates"
+        );
+        assert_eq!(
+            invalid("1,1,1", Format::Cube),
+            "line 1, character 1: \"1,1,1\": coordinates don't sum to zero"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use super::{
+    format::{self, Format},
+    generate, mutate, Error, Hex, Rule,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Class {
+    // Every tile turns white.
+    Empty,
+    StillLife,
+    Oscillator(usize),
+    // The pattern repeats every `period` generations, moved by
+    // `displacement`.
+    Translating { period: usize, displacement: Hex },
+    // Nothing repeated within the generations tried.
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Report {
+    name: String,
+    class: Class,
+    // The generation the pattern first took the shape that repeats, and
+    // the one it was found to repeat in.
+    start: usize,
+    found: usize,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        match self.class {
+            Class::Empty => write!(f, "dies out")?,
+            Class::StillLife => write!(f, "still life")?,
+            Class::Oscillator(period) => write!(f, "oscillator with period {period}")?,
+            Class::Translating {
+                period,
+                displacement,
+            } => write!(
+                f,
+                "moves {} ({}) every {period} generations",
+                format::encode(displacement, Format::Pointy),
+                format::encode(displacement, Format::Axial),
+            )?,
+            Class::Unknown => return write!(f, "unknown after {} generations", self.found),
+        }
+        write!(
+            f,
+            " from generation {}, found at generation {}",
+            self.start, self.found
+        )
+    }
+}
+
+// Moves `tiles` so that the least is at the origin, returning them in
+// order with how far they were moved.
+fn normalise(tiles: &HashSet<Hex>) -> (Vec<Hex>, Hex) {
+    let offset = tiles.iter().min().copied().unwrap_or_default();
+    let tiles = tiles.iter().map(|&tile| tile - offset).sorted().collect();
+    (tiles, offset)
+}
+
+// Evolves `tiles` until their shape repeats, for at most `generations`
+// generations.
+pub(crate) fn classify(
+    tiles: HashSet<Hex>,
+    rule: &Rule,
+    generations: usize,
+) -> (Class, usize, usize) {
+    let mut seen = HashMap::new();
+    let mut tiles = tiles;
+    for generation in 0..=generations {
+        if tiles.is_empty() {
+            return (Class::Empty, generation, generation);
+        }
+        let (shape, offset) = normalise(&tiles);
+        if let Some((start, previous)) = seen.insert(shape, (generation, offset)) {
+            let period = generation - start;
+            let class = match (offset - previous, period) {
+                (displacement, _) if displacement != Hex::default() => Class::Translating {
+                    period,
+                    displacement,
+                },
+                (_, 1) => Class::StillLife,
+                (_, period) => Class::Oscillator(period),
+            };
+            return (class, start, generation);
+        }
+        tiles = mutate(tiles, rule);
+    }
+    (Class::Unknown, generations, generations)
+}
+
+// Classifies the pattern in each file, named after the file.
+pub(crate) fn library(
+    paths: &[impl AsRef<Path>],
+    format: Format,
+    rule: &Rule,
+    generations: usize,
+) -> Result<Vec<Report>, Error> {
+    paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let input = std::fs::read_to_string(path)
+                .map_err(|err| Error::Io(format!("{}: {err}", path.display())))?;
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let (class, start, found) = classify(generate(&input, format)?, rule, generations);
+            Ok(Report {
+                name: name.into_owned(),
+                class,
+                start,
+                found,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify_axial(tiles: &str, rule: &str) -> (Class, usize, usize) {
+        let tiles = generate(&tiles.replace(' ', "\n"), Format::Axial).unwrap();
+        classify(tiles, &rule.parse().unwrap(), 60)
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify_axial("0,0", "B2/S12"), (Class::Empty, 1, 1));
+        assert_eq!(
+            classify_axial("-1,-1 -1,0 -3,1 1,-1", "B2/S12"),
+            (Class::Oscillator(2), 2, 4)
+        );
+        assert_eq!(
+            classify_axial("-1,2 -2,2 -3,2 0,-1", "B2/S12"),
+            (Class::Oscillator(3), 2, 5)
+        );
+        assert_eq!(
+            classify_axial("-1,0 -2,1 1,0 2,-1", "B2/S12"),
+            (Class::Unknown, 60, 60)
+        );
+        assert_eq!(
+            classify_axial("-1,0 -2,1 1,0 2,-1", "B3/S12"),
+            (Class::StillLife, 0, 1)
+        );
+        assert_eq!(
+            classify_axial("-1,0 -2,1 1,-1 2,-1", "B24/S245"),
+            (
+                Class::Translating {
+                    period: 3,
+                    displacement: Hex(-1, 1, 0)
+                },
+                2,
+                5
+            )
+        );
+    }
+
+    #[test]
+    fn test_library() {
+        let dir = std::env::temp_dir().join(format!("day24-patterns-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let glider = dir.join("glider.txt");
+        std::fs::write(&glider, "-1,0\n-2,1\n1,-1\n2,-1\n").unwrap();
+        let rule = "B24/S245".parse().unwrap();
+        let reports = library(&[&glider], Format::Axial, &rule, 10).unwrap();
+        assert_eq!(
+            reports.iter().map(Report::to_string).collect_vec(),
+            ["glider: moves se (0,1) every 3 generations from generation 2, found at generation 5"]
+        );
+        assert!(library(&[dir.join("missing.txt")], Format::Axial, &rule, 10).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashSet, fmt::Write};
+
+use super::Hex;
+
+// The population on one day, measured along the cube axes x, y and z as
+// written by `--format cube`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Stats {
+    black: usize,
+    // Tiles turned black and white since the day before.
+    births: usize,
+    deaths: usize,
+    // The number of rows of tiles the black ones span along each axis.
+    extent: [isize; 3],
+    // The mean position of the black tiles, if there are any.
+    centroid: Option<[f64; 3]>,
+}
+
+impl Stats {
+    fn new(tiles: &HashSet<Hex>, previous: Option<&HashSet<Hex>>) -> Self {
+        let (births, deaths) = previous.map_or((0, 0), |previous| {
+            (
+                tiles.difference(previous).count(),
+                previous.difference(tiles).count(),
+            )
+        });
+        let mut extent = [0; 3];
+        let mut centroid = None;
+        if !tiles.is_empty() {
+            let (mut min, mut max, mut sum) = ([isize::MAX; 3], [isize::MIN; 3], [0; 3]);
+            for &tile in tiles {
+                for (axis, value) in tile.cube().into_iter().enumerate() {
+                    min[axis] = min[axis].min(value);
+                    max[axis] = max[axis].max(value);
+                    sum[axis] += value;
+                }
+            }
+            extent = [0, 1, 2].map(|axis| max[axis] - min[axis] + 1);
+            centroid = Some(sum.map(|sum| sum as f64 / tiles.len() as f64));
+        }
+        Self {
+            black: tiles.len(),
+            births,
+            deaths,
+            extent,
+            centroid,
+        }
+    }
+}
+
+// Measures the tiles on each day, starting from the first.
+pub(crate) fn series(history: &[HashSet<Hex>]) -> Vec<Stats> {
+    history
+        .iter()
+        .enumerate()
+        .map(|(day, tiles)| Stats::new(tiles, day.checked_sub(1).map(|day| &history[day])))
+        .collect()
+}
+
+// Writes one row per day, leaving the centroid empty on days without any
+// black tiles.
+pub(crate) fn csv(series: &[Stats]) -> String {
+    let mut csv = String::from(
+        "day,black,births,deaths,extent_x,extent_y,extent_z,centroid_x,centroid_y,centroid_z\n",
+    );
+    for (day, stats) in series.iter().enumerate() {
+        let [x, y, z] = stats.extent;
+        write!(
+            csv,
+            "{day},{},{},{},{x},{y},{z}",
+            stats.black, stats.births, stats.deaths
+        )
+        .unwrap();
+        match stats.centroid {
+            Some([x, y, z]) => writeln!(csv, ",{x:.3},{y:.3},{z:.3}").unwrap(),
+            None => writeln!(csv, ",,,").unwrap(),
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{generate, mutate, Format, Rule};
+    use super::*;
+
+    #[test]
+    fn test_series() {
+        let rule = Rule::default();
+        let mut history = vec![generate("0,0\n1,0\n0,1", Format::Axial).unwrap()];
+        for _ in 0..2 {
+            history.push(mutate(history.last().unwrap().clone(), &rule));
+        }
+        let series = series(&history);
+        assert_eq!(
+            series[0],
+            Stats {
+                black: 3,
+                births: 0,
+                deaths: 0,
+                extent: [2, 2, 2],
+                centroid: Some([1.0 / 3.0, -2.0 / 3.0, 1.0 / 3.0]),
+            }
+        );
+        for (stats, pair) in series[1..].iter().zip(history.windows(2)) {
+            assert_eq!(stats.black, pair[1].len());
+            assert_eq!(stats.black + stats.deaths, pair[0].len() + stats.births);
+        }
+        let csv = csv(&series);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("day,black,births,deaths,extent_x,extent_y,extent_z,centroid_x,centroid_y,centroid_z")
+        );
+        assert_eq!(lines.next(), Some("0,3,0,0,2,2,2,0.333,-0.667,0.333"));
+        assert_eq!(lines.count(), 2);
+    }
+
+    #[test]
+    fn test_empty() {
+        let series = series(&[generate("0,0", Format::Axial).unwrap(), HashSet::new()]);
+        assert_eq!(csv(&series).lines().last(), Some("1,0,0,1,0,0,0,,,"));
+    }
+}
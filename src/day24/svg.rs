@@ -0,0 +1,130 @@
+use std::{collections::HashSet, f64::consts::PI, fmt::Write};
+
+use itertools::Itertools;
+
+use super::Hex;
+
+// The distance from a tile's centre to its corners, in pixels.
+const SIZE: f64 = 10.0;
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+// Tiles are pointy-topped, with e along the x axis and ne up and to the
+// right. `Hex(i, j, k)` is i steps ne and j steps e.
+fn centre(Hex(i, j, _): Hex) -> (f64, f64) {
+    let (i, j) = (i as f64, j as f64);
+    (SQRT_3 * SIZE * (j + i / 2.0), -1.5 * SIZE * i)
+}
+
+// The area, in pixels, that every frame of an animation shares.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Bounds {
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Bounds {
+    pub(crate) fn new<'a>(tiles: impl IntoIterator<Item = &'a Hex>) -> Self {
+        let (xs, ys): (Vec<_>, Vec<_>) = tiles.into_iter().map(|&tile| centre(tile)).unzip();
+        let range = |values: Vec<f64>| {
+            values
+                .into_iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                })
+        };
+        // An empty floor is framed around the reference tile.
+        let ((min_x, max_x), (min_y, max_y)) = if xs.is_empty() {
+            ((0.0, 0.0), (0.0, 0.0))
+        } else {
+            (range(xs), range(ys))
+        };
+        Self {
+            min: (min_x - 2.0 * SIZE, min_y - 2.0 * SIZE),
+            max: (max_x + 2.0 * SIZE, max_y + 2.0 * SIZE),
+        }
+    }
+}
+
+// Draws the black tiles on a white background.
+pub(crate) fn render(tiles: &HashSet<Hex>, bounds: Bounds) -> String {
+    let (width, height) = (bounds.max.0 - bounds.min.0, bounds.max.1 - bounds.min.1);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.1} {:.1} {width:.1} {height:.1}" width="{width:.0}" height="{height:.0}">"#,
+        bounds.min.0, bounds.min.1
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect x="{:.1}" y="{:.1}" width="{width:.1}" height="{height:.1}" fill="white"/>"#,
+        bounds.min.0, bounds.min.1
+    )
+    .unwrap();
+    // Sort the tiles so that the same tiles always draw the same file.
+    for &tile in tiles.iter().sorted() {
+        let (x, y) = centre(tile);
+        let corners = (0..6)
+            .map(|corner| {
+                let angle = PI / 3.0 * corner as f64 + PI / 6.0;
+                format!(
+                    "{:.1},{:.1}",
+                    x + SIZE * angle.cos(),
+                    y + SIZE * angle.sin()
+                )
+            })
+            .join(" ");
+        writeln!(
+            svg,
+            r#"<polygon points="{corners}" fill="black" stroke="grey" stroke-width="1"/>"#
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{format::Format, generate};
+    use super::*;
+
+    #[test]
+    fn test_centre() {
+        let origin = Hex::default();
+        assert_eq!(centre(origin), (0.0, 0.0));
+        for neighbour in origin.neighbours() {
+            let (x, y) = centre(neighbour);
+            assert!(((x * x + y * y).sqrt() - SQRT_3 * SIZE).abs() < 1e-9);
+        }
+        let e = Hex::try_from("e").unwrap();
+        assert_eq!(centre(e), (SQRT_3 * SIZE, 0.0));
+        let (x, y) = centre(Hex::try_from("ne").unwrap());
+        assert!(x > 0.0 && y < 0.0);
+    }
+
+    #[test]
+    fn test_render() {
+        let tiles = generate("e\nw\nnenw", Format::Pointy).unwrap();
+        let svg = render(&tiles, Bounds::new(&tiles));
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polygon").count(), 3);
+        assert_eq!(svg, render(&tiles, Bounds::new(&tiles)));
+        // Only the tiles are framed, however far they are from the
+        // reference tile.
+        let far = generate("100,100", Format::Axial).unwrap();
+        let around = |(x, y): (f64, f64)| Bounds {
+            min: (x - 2.0 * SIZE, y - 2.0 * SIZE),
+            max: (x + 2.0 * SIZE, y + 2.0 * SIZE),
+        };
+        assert_eq!(Bounds::new(&far), around(centre(Hex::from_axial(100, 100))));
+        assert_eq!(Bounds::new([]), around((0.0, 0.0)));
+        assert_eq!(
+            render(&HashSet::new(), Bounds::new([]))
+                .matches("<polygon")
+                .count(),
+            0
+        );
+    }
+}
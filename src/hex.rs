@@ -0,0 +1,278 @@
+use std::iter;
+
+use derive_more::{Add, Mul, Sub, Sum};
+
+/*      i
+ *      |
+ *    /  \
+ *   |    |
+ *  / \  / \
+ * k         j
+ */
+// A pointy-topped hexagonal tile in cube coordinates, which always sum to
+// zero. `Hex(i, j, k)` is i steps ne and j steps e from the origin.
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Add, Sub, Sum, Mul)]
+pub(crate) struct Hex(pub(crate) isize, pub(crate) isize, pub(crate) isize);
+
+// The steps to each neighbour, clockwise from e.
+pub(crate) const DIRECTIONS: [Hex; 6] = [
+    Hex(0, 1, -1),
+    Hex(-1, 1, 0),
+    Hex(-1, 0, 1),
+    Hex(0, -1, 1),
+    Hex(1, -1, 0),
+    Hex(1, 0, -1),
+];
+
+impl Hex {
+    pub(crate) fn neighbours(self) -> impl IntoIterator<Item = Hex> {
+        DIRECTIONS.map(|step| self + step)
+    }
+
+    // The number of steps between the tiles.
+    pub(crate) fn distance(self, other: Hex) -> isize {
+        let Hex(i, j, k) = self - other;
+        (i.abs() + j.abs() + k.abs()) / 2
+    }
+
+    // The tiles `radius` steps away, clockwise from the one nw of the
+    // centre.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn ring(self, radius: usize) -> impl Iterator<Item = Hex> {
+        let start = self + DIRECTIONS[4] * radius as isize;
+        let sides = DIRECTIONS
+            .into_iter()
+            .flat_map(move |step| iter::repeat_n(step, radius));
+        let ring = sides.scan(start, |hex, step| {
+            let here = *hex;
+            *hex = *hex + step;
+            Some(here)
+        });
+        ring.chain((radius == 0).then_some(self))
+    }
+
+    // The tiles at most `radius` steps away, ring by ring outwards.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn spiral(self, radius: usize) -> impl Iterator<Item = Hex> {
+        (0..=radius).flat_map(move |radius| self.ring(radius))
+    }
+
+    // The tiles whose centres are nearest the straight line between the
+    // tiles, from this one to `other`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn line(self, other: Hex) -> Vec<Hex> {
+        let steps = self.distance(other);
+        // Nudge the line off the edges between tiles so that every point on
+        // it rounds the same way.
+        let [from, to] =
+            [self, other].map(|Hex(i, j, k)| [i as f64 + 1e-6, j as f64 + 1e-6, k as f64 - 2e-6]);
+        (0..=steps)
+            .map(|step| {
+                let t = if steps == 0 {
+                    0.0
+                } else {
+                    step as f64 / steps as f64
+                };
+                round([0, 1, 2].map(|axis| from[axis] + (to[axis] - from[axis]) * t))
+            })
+            .collect()
+    }
+
+    // Turns the tile a sixth of the way around the origin `sixths` times,
+    // clockwise.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn rotate(self, sixths: isize) -> Hex {
+        (0..sixths.rem_euclid(6)).fold(self, |Hex(i, j, k), _| Hex(-j, -k, -i))
+    }
+
+    // Mirrors the tile in the line running n and s through the origin.
+    // Rotating the result gives the other five reflections.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn reflect(self) -> Hex {
+        let Hex(i, j, k) = self;
+        Hex(i, k, j)
+    }
+
+    // Axial coordinates (q, r), with q counting steps e and r steps se.
+    pub(crate) fn axial(self) -> (isize, isize) {
+        (-self.2, -self.0)
+    }
+
+    pub(crate) fn from_axial(q: isize, r: isize) -> Hex {
+        Hex(-r, q + r, -q)
+    }
+
+    // Cube coordinates [x, y, z], with x counting steps e and z steps se.
+    pub(crate) fn cube(self) -> [isize; 3] {
+        [-self.2, -self.1, -self.0]
+    }
+
+    // None unless the coordinates sum to zero.
+    pub(crate) fn from_cube(x: isize, y: isize, z: isize) -> Option<Hex> {
+        (x + y + z == 0).then_some(Hex(-z, -y, -x))
+    }
+
+    // Offset coordinates (column, row) with rows running downwards and odd
+    // rows shifted half a tile right.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn offset(self) -> (isize, isize) {
+        let (q, r) = self.axial();
+        (q + (r - (r & 1)) / 2, r)
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn from_offset(column: isize, row: isize) -> Hex {
+        Hex::from_axial(column - (row - (row & 1)) / 2, row)
+    }
+}
+
+// The tile containing a point in cube coordinates, fixing up whichever
+// coordinate rounded furthest so that they still sum to zero.
+#[cfg_attr(not(test), allow(dead_code))]
+fn round(point: [f64; 3]) -> Hex {
+    let [i, j, k] = point.map(f64::round);
+    let [di, dj, dk] = [0, 1, 2].map(|axis| (point[axis].round() - point[axis]).abs());
+    let (i, j, k) = if di > dj && di > dk {
+        (-j - k, j, k)
+    } else if dj > dk {
+        (i, -i - k, k)
+    } else {
+        (i, j, -i - j)
+    };
+    Hex(i as isize, j as isize, k as isize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use itertools::Itertools;
+
+    use crate::utils::Rng;
+
+    use super::*;
+
+    fn random(rng: &mut Rng, radius: usize) -> Hex {
+        let tiles = Hex::default().spiral(radius).collect_vec();
+        tiles[rng.below(tiles.len())]
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let neighbours: HashSet<_> = Hex::default().neighbours().into_iter().collect();
+        assert_eq!(neighbours, DIRECTIONS.into_iter().collect());
+        assert_eq!(
+            Hex::default().ring(1).collect_vec(),
+            DIRECTIONS.map(|step| step.rotate(4))
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        // Breadth-first search over steps to neighbours finds the shortest
+        // paths.
+        let mut paths = HashMap::from([(Hex::default(), 0)]);
+        let mut frontier = vec![Hex::default()];
+        for steps in 1..=8 {
+            frontier = frontier
+                .into_iter()
+                .flat_map(Hex::neighbours)
+                .filter(|&hex| !paths.contains_key(&hex))
+                .unique()
+                .collect();
+            for &hex in &frontier {
+                paths.insert(hex, steps);
+            }
+        }
+        for (hex, steps) in paths {
+            assert_eq!(hex.distance(Hex::default()), steps, "{hex:?}");
+        }
+        let mut rng = Rng::new(24);
+        for _ in 0..100 {
+            let (a, b) = (random(&mut rng, 10), random(&mut rng, 10));
+            assert_eq!(a.distance(b), b.distance(a));
+            assert_eq!((a - b).distance(Hex::default()), a.distance(b));
+        }
+    }
+
+    #[test]
+    fn test_ring() {
+        let centre = Hex(2, -5, 3);
+        assert_eq!(centre.ring(0).collect_vec(), [centre]);
+        for radius in 1..6 {
+            let ring = centre.ring(radius).collect_vec();
+            assert_eq!(ring.len(), 6 * radius);
+            assert!(ring
+                .iter()
+                .all(|&hex| hex.distance(centre) == radius as isize));
+            assert_eq!(ring.iter().unique().count(), ring.len());
+            for (a, b) in ring.iter().circular_tuple_windows() {
+                assert_eq!(a.distance(*b), 1);
+            }
+        }
+        let spiral = centre.spiral(5).collect_vec();
+        assert_eq!(spiral.len(), 3 * 5 * 6 + 1);
+        assert_eq!(spiral.iter().unique().count(), spiral.len());
+    }
+
+    #[test]
+    fn test_line() {
+        assert_eq!(Hex(1, 2, -3).line(Hex(1, 2, -3)), [Hex(1, 2, -3)]);
+        assert_eq!(
+            Hex::default().line(DIRECTIONS[0] * 3),
+            (0..=3).map(|n| DIRECTIONS[0] * n).collect_vec()
+        );
+        let mut rng = Rng::new(49);
+        for _ in 0..100 {
+            let (a, b) = (random(&mut rng, 10), random(&mut rng, 10));
+            let line = a.line(b);
+            assert_eq!(line.len() as isize, a.distance(b) + 1);
+            assert_eq!((line[0], line[line.len() - 1]), (a, b));
+            for (x, y) in line.iter().tuple_windows() {
+                assert_eq!(x.distance(*y), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetry() {
+        assert_eq!(DIRECTIONS[0].rotate(1), DIRECTIONS[1]);
+        assert_eq!(DIRECTIONS[0].rotate(-1), DIRECTIONS[5]);
+        assert_eq!(DIRECTIONS[0].reflect(), DIRECTIONS[3]);
+        assert_eq!(DIRECTIONS[5].reflect(), DIRECTIONS[4]);
+        let mut rng = Rng::new(60);
+        for _ in 0..100 {
+            let (a, b) = (random(&mut rng, 10), random(&mut rng, 10));
+            assert_eq!(a.rotate(6), a);
+            assert_eq!(a.rotate(2).rotate(-2), a);
+            assert_eq!(a.reflect().reflect(), a);
+            assert_eq!(a.reflect().rotate(1).reflect(), a.rotate(-1));
+            for sixths in 0..6 {
+                assert_eq!(a.rotate(sixths).distance(b.rotate(sixths)), a.distance(b));
+            }
+            assert_eq!(a.reflect().distance(b.reflect()), a.distance(b));
+        }
+    }
+
+    #[test]
+    fn test_coordinates() {
+        assert_eq!(DIRECTIONS[0].axial(), (1, 0));
+        assert_eq!(DIRECTIONS[1].axial(), (0, 1));
+        assert_eq!(DIRECTIONS[0].cube(), [1, -1, 0]);
+        assert_eq!(Hex::from_cube(1, 1, 1), None);
+        // Going se from an even row stays in the same column, but from an
+        // odd row it moves a column right.
+        assert_eq!(DIRECTIONS[1].offset(), (0, 1));
+        assert_eq!((DIRECTIONS[1] * 2).offset(), (1, 2));
+        assert_eq!(Hex::from_offset(-1, -1), DIRECTIONS[4]);
+        assert_eq!(Hex::from_offset(0, -1), DIRECTIONS[5]);
+        for hex in Hex::default().spiral(6) {
+            let (q, r) = hex.axial();
+            assert_eq!(Hex::from_axial(q, r), hex);
+            let [x, y, z] = hex.cube();
+            assert_eq!(Hex::from_cube(x, y, z), Some(hex));
+            let (column, row) = hex.offset();
+            assert_eq!(Hex::from_offset(column, row), hex);
+        }
+    }
+}
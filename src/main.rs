@@ -1,5 +1,6 @@
 use clap::Parser;
 
+mod hex;
 mod utils;
 
 #[derive(Parser, Debug)]
@@ -7,16 +8,34 @@ mod utils;
 struct Args {
     #[arg(value_enum, default_value_t=Task::Latest)]
     task: Task,
+    #[command(flatten, next_help_heading = "Day 22")]
+    day22: day22::Options,
+    #[command(flatten, next_help_heading = "Day 23")]
+    day23: day23::Options,
+    #[command(flatten, next_help_heading = "Day 24")]
+    day24: day24::Options,
 }
 
 utils::make_runner!(
     21+,
-    22+,
-    23+,
-    24+,
+    22+ with options,
+    23+ with options,
+    24+ with options,
     25,
 );
 
 fn main() {
     run(Args::parse());
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        Args::command().debug_assert();
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 macro_rules! make_runner {
     (@helper
         { $($mods:tt)* }
@@ -16,9 +18,9 @@ macro_rules! make_runner {
                 $($labels)*
             }
             {
-                Task::[< Day $day >] => {
+                Task::[< Day $day >] => |_: &Args| {
                     let input = include_str!(concat!("../inputs/", $day, ".txt"));
-                    (stringify!($day), [< day $day >]::solve(input).to_string())
+                    (stringify!($day), Ok([< day $day >]::solve(input).to_string()))
                 },
                 $($arms)*
             }
@@ -43,13 +45,48 @@ macro_rules! make_runner {
                 $($labels)*
             }
             {
-                Task::[< Day $day _2 >] => {
+                Task::[< Day $day _2 >] => |_: &Args| {
                     let input = include_str!(concat!("../inputs/", $day, ".txt"));
-                    (concat!($day, " (part 2)"), [< day $day >]::solve_2(input).to_string())
+                    (concat!($day, " (part 2)"), Ok([< day $day >]::solve_2(input).to_string()))
                 },
-                Task::[< Day $day >] => {
+                Task::[< Day $day >] => |_: &Args| {
                     let input = include_str!(concat!("../inputs/", $day, ".txt"));
-                    (stringify!($day), [< day $day >]::solve(input).to_string())
+                    (stringify!($day), Ok([< day $day >]::solve(input).to_string()))
+                },
+                $($arms)*
+            }
+            $($rest)*
+        ); }
+    );
+    // Like `$day+`, but passes `args.day$day` (the day's flattened
+    // `Options`) to `solve_with` and `solve_2_with`, which return Results.
+    (@helper
+        { $($mods:tt)* }
+        { $($labels:tt)* }
+        { $($arms:tt)* }
+        $day:tt + with options,
+        $($rest:tt)*
+    ) => (
+        paste::paste! { crate::utils::make_runner!(@helper
+            {
+                $($mods)*
+                mod [<day $day>];
+            }
+            {
+                [< Day $day _2 >],
+                [< Day $day >],
+                $($labels)*
+            }
+            {
+                Task::[< Day $day _2 >] => |args: &Args| {
+                    let input = include_str!(concat!("../inputs/", $day, ".txt"));
+                    let options = &args.[< day $day >];
+                    (concat!($day, " (part 2)"), crate::utils::answer([< day $day >]::solve_2_with(input, options)))
+                },
+                Task::[< Day $day >] => |args: &Args| {
+                    let input = include_str!(concat!("../inputs/", $day, ".txt"));
+                    let options = &args.[< day $day >];
+                    (stringify!($day), crate::utils::answer([< day $day >]::solve_with(input, options)))
                 },
                 $($arms)*
             }
@@ -67,14 +104,23 @@ macro_rules! make_runner {
         $($mods)*
 
         fn run(args: Args) {
-            let start = std::time::Instant::now();
-            let (day, result) = match args.task {
+            let solver: fn(&Args) -> (&str, Result<String, String>) = match args.task {
                 $lhs => $rhs,
                 Task::Latest => $rhs,
                 $($rest_lhs => $rest_rhs,)*
             };
+            let start = std::time::Instant::now();
+            let (day, result) = solver(&args);
             let duration = start.elapsed().as_secs_f32();
-            println!("Computed result for day {day} in {duration:.3} seconds: {result}");
+            match result {
+                Ok(result) => {
+                    println!("Computed result for day {day} in {duration:.3} seconds: {result}")
+                }
+                Err(err) => {
+                    eprintln!("Failed to compute result for day {day}: {err}");
+                    std::process::exit(1);
+                }
+            }
         }
     );
 
@@ -84,3 +130,38 @@ macro_rules! make_runner {
 }
 
 pub(crate) use make_runner;
+
+pub(crate) fn answer<T: Display, E: Display>(result: Result<T, E>) -> Result<String, String> {
+    result
+        .map(|answer| answer.to_string())
+        .map_err(|err| err.to_string())
+}
+
+// SplitMix64, a small seedable generator: simulations only need to be
+// reproducible, not cryptographically random.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A uniformly distributed value in 0..n.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}